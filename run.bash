@@ -31,7 +31,7 @@ echo "=== Building Backend (Rust) ==="
 cd /build
 cargo build --release --target wasm32-unknown-unknown
 echo "Publishing modules..."
-APP_ID=$(linera publish-and-create target/wasm32-unknown-unknown/release/quiz_{contract,service}.wasm)
+APP_ID=$(linera publish-and-create target/wasm32-unknown-unknown/release/quiz_{contract,service}.wasm --json-argument "{\"admins\":[\"$OWNER\"]}")
 
 # Save CHAIN_ID and APP_ID to .env file for frontend use
 ENV_FILE="/build/front-end/.env"
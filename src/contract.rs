@@ -3,17 +3,25 @@
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
-mod state;
-
-use linera_sdk::linera_base_types::TimeDelta;
+use linera_sdk::linera_base_types::{Account, AccountOwner, Amount, TimeDelta, Timestamp};
 use linera_sdk::{
     linera_base_types::WithContractAbi,
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 
-use crate::state::{Question, QuizSet, QuizState, UserAttempt};
-use quiz::{CreateQuizParams, LeaderboardEntry, Operation, SubmitAnswersParams};
+use quiz::state::{
+    AppEvent, Badge, Duel, LiveAnswer, Question, QuizSet, QuizState, Rating, Report, Team,
+    UserAttempt,
+};
+use quiz::{
+    AcceptDuelParams, ActivityKind, AppConfig, ArchivedQuizSummary, BadgeKind, BankQuestionParams,
+    CloneQuizParams, CreateDuelParams, CreateQuizParams, CreateTeamParams, DeliveryMode,
+    DuelStatus, ImportQuizParams, JoinTeamParams, LeaderboardEntry, ModerateQuizParams,
+    ModerationAction, Operation, QuestionParams, QuizExport, RateQuizParams, ReportQuizParams,
+    SubmitAnswersParams, SubmitLiveAnswerParams, MAX_RATING_COMMENT_CHARS, MAX_REPORT_REASON_CHARS,
+};
+use std::cmp::Ordering;
 
 pub struct QuizContract {
     state: QuizState,
@@ -28,7 +36,7 @@ impl WithContractAbi for QuizContract {
 
 impl Contract for QuizContract {
     type Message = ();
-    type InstantiationArgument = ();
+    type InstantiationArgument = AppConfig;
     type Parameters = ();
     type EventValue = ();
 
@@ -39,12 +47,16 @@ impl Contract for QuizContract {
         QuizContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, _argument: ()) {
+    async fn instantiate(&mut self, argument: AppConfig) {
         // 初始化下一个Quiz ID为1
         let current_value = self.state.next_quiz_id.get();
         if *current_value == 0 {
             self.state.next_quiz_id.set(1);
         }
+        argument
+            .validate()
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.state.config.set(argument);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
@@ -55,6 +67,69 @@ impl Contract for QuizContract {
             Operation::SubmitAnswers(params) => {
                 self.submit_answers(params).await;
             }
+            Operation::CloneQuiz(params) => {
+                self.clone_quiz(params).await;
+            }
+            Operation::ImportQuiz(params) => {
+                self.import_quiz(params).await;
+            }
+            Operation::AddBankQuestion(params) => {
+                self.save_bank_question(params, false).await;
+            }
+            Operation::UpdateBankQuestion(params) => {
+                self.save_bank_question(params, true).await;
+            }
+            Operation::DeleteBankQuestion(bank_question_id) => {
+                self.delete_bank_question(bank_question_id).await;
+            }
+            Operation::ArchiveQuiz(quiz_id) => {
+                self.archive_quiz(quiz_id).await;
+            }
+            Operation::CreateDuel(params) => {
+                self.create_duel(params).await;
+            }
+            Operation::AcceptDuel(params) => {
+                self.accept_duel(params).await;
+            }
+            Operation::CreateTeam(params) => {
+                self.create_team(params).await;
+            }
+            Operation::JoinTeam(params) => {
+                self.join_team(params).await;
+            }
+            Operation::RevealNextQuestion(quiz_id) => {
+                self.reveal_next_question(quiz_id).await;
+            }
+            Operation::CloseCurrentQuestion(quiz_id) => {
+                self.close_current_question(quiz_id).await;
+            }
+            Operation::SubmitLiveAnswer(params) => {
+                self.submit_live_answer(params).await;
+            }
+            Operation::RateQuiz(params) => {
+                self.rate_quiz(params).await;
+            }
+            Operation::ReportQuiz(params) => {
+                self.report_quiz(params).await;
+            }
+            Operation::ModerateQuiz(params) => {
+                self.moderate_quiz(params).await;
+            }
+            Operation::UpdateConfig(config) => {
+                self.update_config(config);
+            }
+            Operation::DistributePrizes(quiz_id) => {
+                self.distribute_prizes(quiz_id).await;
+            }
+            Operation::RefundEntries(quiz_id) => {
+                self.refund_entries(quiz_id).await;
+            }
+            Operation::FinalizeLiveQuiz(quiz_id) => {
+                self.finalize_closed_live_quiz(quiz_id).await;
+            }
+            Operation::PruneEventsBefore(cutoff_millis) => {
+                self.prune_events_before(cutoff_millis).await;
+            }
         }
     }
 
@@ -72,63 +147,114 @@ impl QuizContract {
         let current_time = self.runtime.system_time();
 
         // 验证测验时间范围
-        let start_time_millis = params
-            .start_time
-            .parse::<u64>()
-            .expect("Invalid start time format");
+        let start_time = quiz::millis_to_timestamp(
+            params.start_time_millis,
+            params.start_time.as_deref(),
+            "start time",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        let end_time = quiz::millis_to_timestamp(
+            params.end_time_millis,
+            params.end_time.as_deref(),
+            "end time",
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        self.validate_time_range(start_time, end_time);
 
-        // 检查时间戳长度是否合理（毫秒级时间戳应该是13位左右）
+        // 验证标签：最多5个，每个非空且不超过32个字符
+        let tags: Vec<String> = params
+            .tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .collect();
+        assert!(tags.len() <= 5, "Too many tags (maximum 5)");
         assert!(
-            start_time_millis.to_string().len() >= 10 && start_time_millis.to_string().len() <= 14,
-            "Start time seems invalid (should be a millisecond timestamp)"
+            tags.iter()
+                .all(|tag| !tag.is_empty() && tag.chars().count() <= 32),
+            "Tags must be non-empty and at most 32 characters"
         );
 
-        let start_time: linera_sdk::linera_base_types::Timestamp = start_time_millis
-            .checked_mul(1000)
-            .expect("Start time overflow when converting to microseconds")
-            .into(); // 毫秒转微秒
-
-        let end_time_millis = params
-            .end_time
-            .parse::<u64>()
-            .expect("Invalid end time format");
-
-        // 检查时间戳长度是否合理（毫秒级时间戳应该是13位左右）
         assert!(
-            end_time_millis.to_string().len() >= 10 && end_time_millis.to_string().len() <= 14,
-            "End time seems invalid (should be a millisecond timestamp)"
+            params.max_participants != Some(0),
+            "Max participants must be at least 1"
         );
 
-        let end_time: linera_sdk::linera_base_types::Timestamp = end_time_millis
-            .checked_mul(1000)
-            .expect("End time overflow when converting to microseconds")
-            .into(); // 毫秒转微秒
+        let quiz_id = *self.state.next_quiz_id.get();
+        let creator = self.claim_nick_name(&params.nick_name).await;
+        let creator_owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
 
-        assert!(
-            start_time > current_time,
-            "Start time must be in the future"
+        // 复制引用的题库问题，之后修改题库不会影响此测验
+        let mut questions = params.questions;
+        for bank_question_id in params.bank_question_ids {
+            let question = self
+                .state
+                .question_bank
+                .get(&(creator_owner, bank_question_id.clone()))
+                .await
+                .expect("Failed to get bank question")
+                .unwrap_or_else(|| panic!("Bank question not found: {bank_question_id}"));
+            questions.push(question);
+        }
+
+        if let Some(image_url) = &params.image_url {
+            quiz::validate_url(image_url, "Quiz image URL")
+                .unwrap_or_else(|error| panic!("{error}"));
+        }
+        questions.iter().for_each(validate_question);
+
+        // 检查问题数量和选项数量上限
+        self.check_question_limits(
+            questions.len(),
+            questions.iter().map(|q| q.options.len()).max().unwrap_or(0),
         );
-        assert!(end_time > start_time, "End time must be after start time");
-        // 检查时间范围是否合理（不超过100年）
+
         assert!(
-            end_time.delta_since(start_time) <= TimeDelta::from_secs(3600 * 24 * 365 * 100),
-            "Time range is too long (maximum 100 years)"
+            params.grace_period_secs <= 3600 * 24,
+            "Grace period must be at most 24 hours"
         );
 
-        let quiz_id = *self.state.next_quiz_id.get();
-        let _creator_owner = self
-            .runtime
-            .authenticated_signer()
-            .expect("Failed to get authenticated signer: no user authenticated");
-        let creator = params.nick_name.clone();
+        // 每题分值为1到1000，总分不能超过 u32 范围
+        let total_points =
+            quiz::total_question_points(&questions).unwrap_or_else(|error| panic!("{error}"));
+
+        // 奖金从创建者账户转入应用账户托管，测验结束后与报名费一起发放
+        let prize = params.prize.unwrap_or(Amount::ZERO);
+        let entry_fee = params.entry_fee.unwrap_or(Amount::ZERO);
+        if prize > Amount::ZERO || entry_fee > Amount::ZERO {
+            assert!(
+                !params.prize_split.is_empty() && params.prize_split.len() <= 3,
+                "Prize split must cover 1 to 3 places"
+            );
+            assert!(
+                params
+                    .prize_split
+                    .iter()
+                    .map(|&p| u32::from(p))
+                    .sum::<u32>()
+                    == 100,
+                "Prize split must sum to 100"
+            );
+            if prize > Amount::ZERO {
+                let destination = self.application_account();
+                self.runtime.transfer(creator_owner, destination, prize);
+            }
+        } else {
+            assert!(
+                params.prize_split.is_empty(),
+                "Prize split requires a prize"
+            );
+        }
 
         let quiz_set = QuizSet {
             id: quiz_id,
             title: params.title,
             description: params.description,
             creator,
-            questions: params
-                .questions
+            creator_owner,
+            questions: questions
                 .into_iter()
                 .enumerate()
                 .map(|(i, q)| Question {
@@ -137,14 +263,271 @@ impl QuizContract {
                     options: q.options,
                     correct_options: q.correct_options,
                     points: q.points,
+                    image_url: q.image_url,
+                    option_images: q.option_images,
+                    media_url: q.media_url,
+                    single_choice: q.single_choice.unwrap_or(false),
                 })
                 .collect(),
             time_limit: params.time_limit,
             start_time,
             end_time,
             created_at: current_time,
+            tags,
+            max_participants: params.max_participants,
+            allow_cloning: params.allow_cloning,
+            image_url: params.image_url,
+            penalty: params.penalty,
+            team_ranking: params.team_ranking,
+            delivery_mode: params.delivery_mode,
+            grace_period_secs: params.grace_period_secs,
+            allow_creator_participation: params.allow_creator_participation,
+            total_points,
+            rating_count: 0,
+            rating_total: 0,
+            hidden: false,
+            report_count: 0,
+            prize,
+            prize_split: params.prize_split,
+            prize_distributed: false,
+            entry_fee,
+            pool_total: prize,
+            cancelled: false,
+            revealed_questions: 0,
+            question_open: false,
+            question_revealed_at: current_time,
+        };
+
+        self.insert_quiz(quiz_set).await;
+    }
+
+    async fn clone_quiz(&mut self, params: CloneQuizParams) {
+        let source = self
+            .state
+            .quiz_sets
+            .get(&params.source_quiz_id)
+            .await
+            .expect("Failed to retrieve quiz from storage")
+            .expect("QuizSet not found");
+        let creator = self.claim_nick_name(&params.nick_name).await;
+        let creator_owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(
+            source.allow_cloning || source.creator_owner == creator_owner,
+            "Only the creator can clone this quiz"
+        );
+        // 进行中的测验不能复制，否则可以从副本中读取正确答案
+        assert!(!source.hidden, "Quiz is hidden");
+        assert!(
+            self.is_closed(&source),
+            "Quiz can only be cloned after it has ended"
+        );
+        // 配置上限可能在源测验创建后被调低
+        self.check_question_limits(
+            source.questions.len(),
+            source
+                .questions
+                .iter()
+                .map(|q| q.options.len())
+                .max()
+                .unwrap_or(0),
+        );
+
+        let start_time =
+            quiz::millis_to_timestamp(Some(params.start_time_millis), None, "start time")
+                .unwrap_or_else(|error| panic!("{error}"));
+        let end_time = quiz::millis_to_timestamp(Some(params.end_time_millis), None, "end time")
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.validate_time_range(start_time, end_time);
+
+        // 只复制测验定义，参与记录和答题记录不复制
+        let quiz_set = QuizSet {
+            id: *self.state.next_quiz_id.get(),
+            title: params.title,
+            description: source.description,
+            creator,
+            creator_owner,
+            questions: source.questions,
+            time_limit: source.time_limit,
+            start_time,
+            end_time,
+            created_at: self.runtime.system_time(),
+            tags: source.tags,
+            max_participants: source.max_participants,
+            allow_cloning: false,
+            image_url: source.image_url,
+            penalty: source.penalty,
+            team_ranking: source.team_ranking,
+            delivery_mode: source.delivery_mode,
+            grace_period_secs: source.grace_period_secs,
+            allow_creator_participation: source.allow_creator_participation,
+            total_points: source.total_points,
+            rating_count: 0,
+            rating_total: 0,
+            hidden: false,
+            report_count: 0,
+            prize: Amount::ZERO,
+            prize_split: Vec::new(),
+            prize_distributed: false,
+            entry_fee: Amount::ZERO,
+            pool_total: Amount::ZERO,
+            cancelled: false,
+            revealed_questions: 0,
+            question_open: false,
+            question_revealed_at: start_time,
         };
 
+        self.insert_quiz(quiz_set).await;
+    }
+
+    async fn import_quiz(&mut self, params: ImportQuizParams) {
+        let export =
+            QuizExport::from_json(&params.payload).unwrap_or_else(|error| panic!("{error}"));
+
+        // 复用创建测验的全部校验
+        self.create_quiz(CreateQuizParams {
+            title: export.title,
+            description: export.description,
+            questions: export.questions,
+            time_limit: export.time_limit,
+            start_time: None,
+            end_time: None,
+            nick_name: params.nick_name,
+            tags: export.tags,
+            max_participants: export.max_participants,
+            start_time_millis: Some(params.start_time_millis),
+            end_time_millis: Some(params.end_time_millis),
+            allow_cloning: false,
+            bank_question_ids: Vec::new(),
+            image_url: export.image_url,
+            penalty: export.penalty,
+            team_ranking: export.team_ranking,
+            delivery_mode: export.delivery_mode,
+            grace_period_secs: export.grace_period_secs,
+            allow_creator_participation: export.allow_creator_participation,
+            prize: None,
+            prize_split: Vec::new(),
+            entry_fee: None,
+        })
+        .await;
+    }
+
+    /// 添加或更新当前签名账户题库中的问题
+    async fn save_bank_question(&mut self, params: BankQuestionParams, update: bool) {
+        let owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        let bank_question_id = params.bank_question_id.trim().to_string();
+        assert!(
+            !bank_question_id.is_empty() && bank_question_id.chars().count() <= 64,
+            "Bank question ID must be non-empty and at most 64 characters"
+        );
+        validate_question(&params.question);
+
+        let key = (owner, bank_question_id);
+        let exists = self
+            .state
+            .question_bank
+            .contains_key(&key)
+            .await
+            .expect("Failed to check bank question");
+        if update {
+            assert!(exists, "Bank question not found");
+        } else {
+            assert!(!exists, "Bank question ID already exists");
+        }
+
+        self.state
+            .question_bank
+            .insert(&key, params.question)
+            .expect("Failed to save bank question");
+    }
+
+    async fn delete_bank_question(&mut self, bank_question_id: String) {
+        let owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        let key = (owner, bank_question_id.trim().to_string());
+        assert!(
+            self.state
+                .question_bank
+                .contains_key(&key)
+                .await
+                .expect("Failed to check bank question"),
+            "Bank question not found"
+        );
+        self.state
+            .question_bank
+            .remove(&key)
+            .expect("Failed to delete bank question");
+    }
+
+    /// 存储新测验，记录到创建者的测验列表并更新下一个Quiz ID
+    async fn insert_quiz(&mut self, quiz_set: QuizSet) {
+        let quiz_id = quiz_set.id;
+
+        // 限制每个账户24小时内创建的测验数量，同时清理过期记录
+        let mut timestamps = self
+            .state
+            .creation_timestamps
+            .get(&quiz_set.creator_owner)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        timestamps.retain(|timestamp| {
+            quiz_set.created_at.delta_since(*timestamp) < TimeDelta::from_secs(3600 * 24)
+        });
+        let max_per_day = self.state.config.get().max_quizzes_per_user_per_day;
+        assert!(
+            timestamps.len() < max_per_day as usize,
+            "Rate limited: at most {max_per_day} quizzes per day"
+        );
+        timestamps.push(quiz_set.created_at);
+        let _ = self
+            .state
+            .creation_timestamps
+            .insert(&quiz_set.creator_owner, timestamps);
+
+        // 记录创建者的测验
+        let mut created_quizzes = self
+            .state
+            .user_created_quizzes
+            .get(&quiz_set.creator)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        created_quizzes.push(quiz_id);
+        let _ = self
+            .state
+            .user_created_quizzes
+            .insert(&quiz_set.creator, created_quizzes);
+        let mut owner_quizzes = self
+            .state
+            .owner_created_quizzes
+            .get(&quiz_set.creator_owner)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        owner_quizzes.push(quiz_id);
+        let _ = self
+            .state
+            .owner_created_quizzes
+            .insert(&quiz_set.creator_owner, owner_quizzes);
+
+        // 记录动态
+        self.record_app_event(AppEvent {
+            kind: ActivityKind::QuizCreated,
+            quiz_id,
+            user: quiz_set.creator.clone(),
+            timestamp: quiz_set.created_at,
+        });
+        self.award_badge(&quiz_set.creator, BadgeKind::Creator, quiz_id)
+            .await;
+
         // 存储新Quiz
         let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set);
         // 更新下一个Quiz ID
@@ -152,14 +535,193 @@ impl QuizContract {
         self.state.next_quiz_id.set(next_id);
     }
 
+    async fn archive_quiz(&mut self, quiz_id: u64) {
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(
+            quiz_set.can_be_managed_by(&signer, self.state.config.get()),
+            "Only the creator or an admin can archive this quiz"
+        );
+        let archive_after_days = self.state.config.get().archive_after_days;
+        let archive_after = TimeDelta::from_secs(3600 * 24 * u64::from(archive_after_days));
+        assert!(
+            self.is_ended(&quiz_set)
+                && self.runtime.system_time().delta_since(quiz_set.end_time) >= archive_after,
+            "Quiz cannot be archived yet"
+        );
+        assert!(
+            quiz_set.pool_total == Amount::ZERO || quiz_set.prize_distributed,
+            "Prizes must be distributed before archiving"
+        );
+        self.auto_finalize_live_quiz(&mut quiz_set).await;
+
+        // 删除答题记录，同时收集成绩用于前三名
+        let participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get quiz participants")
+            .unwrap_or_default();
+        let mut entries = Vec::new();
+        for user in &participants {
+            let key = (quiz_id, user.clone());
+            if let Some(attempt) = self
+                .state
+                .user_attempts
+                .get(&key)
+                .await
+                .expect("Failed to get user attempt")
+            {
+                entries.push(LeaderboardEntry {
+                    score_percent: quiz::score_percent(attempt.score, quiz_set.total_points),
+                    user: attempt.user,
+                    score: attempt.score,
+                    time_taken: attempt.time_taken,
+                });
+            }
+            self.state
+                .user_attempts
+                .remove(&key)
+                .expect("Failed to remove user attempt");
+        }
+        entries.sort_by(|a, b| {
+            quiz::leaderboard_key(a.score, a.time_taken, &a.user).cmp(&quiz::leaderboard_key(
+                b.score,
+                b.time_taken,
+                &b.user,
+            ))
+        });
+        entries.truncate(3);
+
+        let summary = ArchivedQuizSummary {
+            quiz_id,
+            title: quiz_set.title,
+            creator: quiz_set.creator,
+            participant_count: participants.len() as u32,
+            top_entries: entries,
+            end_time_micros: quiz_set.end_time.micros(),
+        };
+        let _ = self.state.archived_quizzes.insert(&quiz_id, summary);
+        let _ = self.state.quiz_sets.remove(&quiz_id);
+        self.clear_reports(quiz_id).await;
+        self.clear_quiz_records(quiz_id, &participants).await;
+    }
+
+    /// 删除测验的参与者、参与记录、评价、排行榜、答题用时、团队、对战、实时作答和参与者账户。
+    /// 答题记录由调用者决定是否保留
+    async fn clear_quiz_records(&mut self, quiz_id: u64, participants: &[String]) {
+        for user in participants {
+            let _ = self.state.quiz_ratings.remove(&(quiz_id, user.clone()));
+            let _ = self
+                .state
+                .participant_owners
+                .remove(&(quiz_id, user.clone()));
+            let mut quiz_ids = self
+                .state
+                .user_participations
+                .get(user)
+                .await
+                .expect("Failed to get user participations")
+                .unwrap_or_default();
+            quiz_ids.retain(|&id| id != quiz_id);
+            if quiz_ids.is_empty() {
+                let _ = self.state.user_participations.remove(user);
+            } else {
+                let _ = self.state.user_participations.insert(user, quiz_ids);
+            }
+        }
+        let _ = self.state.quiz_participants.remove(&quiz_id);
+        let _ = self.state.leaderboard.remove(&quiz_id);
+        let _ = self.state.quiz_times.remove(&quiz_id);
+
+        // 团队成员可能没有提交答案，按团队成员删除所属关系
+        let team_count = self
+            .state
+            .team_counts
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get team count")
+            .unwrap_or(0);
+        for team_id in 0..team_count {
+            let team = self
+                .state
+                .quiz_teams
+                .get(&(quiz_id, team_id))
+                .await
+                .expect("Failed to get team");
+            for member in team.map(|team| team.members).unwrap_or_default() {
+                let _ = self.state.user_team.remove(&(quiz_id, member));
+            }
+            let _ = self.state.quiz_teams.remove(&(quiz_id, team_id));
+        }
+        let _ = self.state.team_counts.remove(&quiz_id);
+
+        let mut duels = Vec::new();
+        self.state
+            .duels
+            .for_each_index_value(|duel_id, duel| {
+                if duel.quiz_id == quiz_id {
+                    duels.push((duel_id, [duel.challenger.clone(), duel.opponent.clone()]));
+                }
+                Ok(())
+            })
+            .await
+            .expect("Failed to read duels");
+        for (duel_id, users) in duels {
+            for user in users {
+                let mut duel_ids = self
+                    .state
+                    .user_duels
+                    .get(&user)
+                    .await
+                    .expect("Failed to get user duels")
+                    .unwrap_or_default();
+                duel_ids.retain(|&id| id != duel_id);
+                if duel_ids.is_empty() {
+                    let _ = self.state.user_duels.remove(&user);
+                } else {
+                    let _ = self.state.user_duels.insert(&user, duel_ids);
+                }
+            }
+            let _ = self.state.duels.remove(&duel_id);
+        }
+
+        // 实时模式的作答在最后一题结束时已转为答题记录，未结束时在此删除。
+        // 只在实时模式中作答过的参与者也记录了付费账户
+        let live_participants = self
+            .state
+            .live_participants
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get live participants")
+            .unwrap_or_default();
+        for user in live_participants {
+            let key = (quiz_id, user);
+            let _ = self.state.live_answers.remove(&key);
+            let _ = self.state.participant_owners.remove(&key);
+        }
+        let _ = self.state.live_participants.remove(&quiz_id);
+    }
+
     async fn submit_answers(&mut self, params: SubmitAnswersParams) {
-        let user = params.nick_name.clone();
+        let user = self.claim_nick_name(&params.nick_name).await;
 
         let quiz_id = params.quiz_id;
         let now = self.runtime.system_time();
 
         // 检查Quiz是否存在
-        let quiz_set = self
+        let mut quiz_set = self
             .state
             .quiz_sets
             .get(&quiz_id)
@@ -169,7 +731,14 @@ impl QuizContract {
 
         // 检查测验时间范围
         assert!(now >= quiz_set.start_time, "Quiz has not started yet");
-        assert!(now <= quiz_set.end_time, "Quiz has ended");
+        assert!(!self.is_closed(&quiz_set), "Quiz has ended");
+        assert!(
+            quiz_set.delivery_mode == DeliveryMode::AllAtOnce,
+            "Live quizzes accept answers one question at a time"
+        );
+        assert!(!quiz_set.hidden, "Quiz is hidden");
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+        self.check_creator_participation(&quiz_set);
 
         // 检查用户是否已提交过该Quiz
         if self
@@ -183,6 +752,16 @@ impl QuizContract {
             panic!("User has already attempted this quiz");
         }
 
+        // 检查参与人数是否已达上限
+        let participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        assert!(!quiz_set.is_full(participants.len()), "Quiz is full");
+
         // 验证答案数量是否匹配问题数量
         assert_eq!(
             params.answers.len(),
@@ -190,30 +769,265 @@ impl QuizContract {
             "Answer count mismatch with questions"
         );
 
-        // 计算得分
-        let mut score = 0;
-        for (i, user_answers) in params.answers.iter().enumerate() {
-            let question = &quiz_set.questions[i];
+        // 每题用时为可选项，提供时数量必须与答案一致且总和不超过总用时
+        if !params.per_question_times.is_empty() {
+            assert_eq!(
+                params.per_question_times.len(),
+                params.answers.len(),
+                "Per-question time count mismatch with answers"
+            );
+            let total = params
+                .per_question_times
+                .iter()
+                .try_fold(0u64, |total, &time| total.checked_add(time))
+                .expect("Per-question times overflow");
+            assert!(
+                total <= params.time_taken,
+                "Per-question times exceed total time taken"
+            );
+        }
+
+        // 乱序提交的答案需要先还原为原始顺序再计分
+        let (answers, per_question_times) = if params.shuffled {
+            let per_question_times = if params.per_question_times.is_empty() {
+                Vec::new()
+            } else {
+                quiz::unshuffle_times(&quiz_set, &user, params.per_question_times)
+            };
+            (
+                quiz::unshuffle_answers(&quiz_set, &user, params.answers)
+                    .unwrap_or_else(|error| panic!("{error}")),
+                per_question_times,
+            )
+        } else {
+            (params.answers, params.per_question_times)
+        };
+
+        self.record_participant_owner(quiz_id, &user);
+        self.collect_entry_fee(&mut quiz_set);
+        self.record_attempt(
+            &quiz_set,
+            user,
+            answers,
+            params.time_taken,
+            per_question_times,
+        )
+        .await;
+    }
+
+    /// 获取实时模式的测验并检查调用者是测验创建者
+    async fn live_quiz_for_creator(&mut self, quiz_id: u64) -> QuizSet {
+        let quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(
+            quiz_set.delivery_mode == DeliveryMode::Live,
+            "Quiz is not in live mode"
+        );
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(
+            quiz_set.creator_owner == signer,
+            "Only the creator can control a live quiz"
+        );
+        quiz_set
+    }
+
+    async fn reveal_next_question(&mut self, quiz_id: u64) {
+        let mut quiz_set = self.live_quiz_for_creator(quiz_id).await;
+        let now = self.runtime.system_time();
+        assert!(now >= quiz_set.start_time, "Quiz has not started yet");
+        assert!(!self.is_ended(&quiz_set), "Quiz has ended");
+        assert!(
+            !quiz_set.question_open,
+            "Close the current question before revealing the next one"
+        );
+        assert!(
+            (quiz_set.revealed_questions as usize) < quiz_set.questions.len(),
+            "All questions have been revealed"
+        );
+
+        quiz_set.revealed_questions += 1;
+        quiz_set.question_open = true;
+        quiz_set.question_revealed_at = now;
+        let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set);
+    }
+
+    async fn close_current_question(&mut self, quiz_id: u64) {
+        let mut quiz_set = self.live_quiz_for_creator(quiz_id).await;
+        assert!(quiz_set.question_open, "No question is open");
+        quiz_set.question_open = false;
+        let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set.clone());
+
+        if quiz_set.revealed_questions as usize == quiz_set.questions.len() {
+            self.finalize_live_quiz(&quiz_set).await;
+        }
+    }
+
+    async fn submit_live_answer(&mut self, params: SubmitLiveAnswerParams) {
+        let user = self.claim_nick_name(&params.nick_name).await;
+        let quiz_id = params.quiz_id;
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to retrieve quiz from storage")
+            .expect("QuizSet not found");
+        assert!(
+            quiz_set.delivery_mode == DeliveryMode::Live,
+            "Quiz is not in live mode"
+        );
+        assert!(!self.is_closed(&quiz_set), "Quiz has ended");
+        assert!(
+            quiz_set.question_open && params.question_id + 1 == quiz_set.revealed_questions,
+            "Question is not open for answers"
+        );
+        assert!(!quiz_set.hidden, "Quiz is hidden");
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+        self.check_creator_participation(&quiz_set);
+
+        let key = (quiz_id, user.clone());
+        let mut answers = match self.state.live_answers.get(&key).await.unwrap() {
+            Some(answers) => answers,
+            None => {
+                // 首次作答时检查参与人数上限并记录参与者
+                let mut participants = self
+                    .state
+                    .live_participants
+                    .get(&quiz_id)
+                    .await
+                    .unwrap()
+                    .unwrap_or_default();
+                assert!(!quiz_set.is_full(participants.len()), "Quiz is full");
+                self.record_participant_owner(quiz_id, &user);
+                self.collect_entry_fee(&mut quiz_set);
+                participants.push(user);
+                let _ = self.state.live_participants.insert(&quiz_id, participants);
+                vec![None; quiz_set.questions.len()]
+            }
+        };
+        let answer = &mut answers[params.question_id as usize];
+        assert!(answer.is_none(), "Question has already been answered");
+
+        // 与最终计分使用相同的校验，避免结束最后一题时计分失败
+        quiz::validate_selection(
+            &quiz_set.questions[params.question_id as usize],
+            &params.selected_answers,
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        let elapsed = self
+            .runtime
+            .system_time()
+            .delta_since(quiz_set.question_revealed_at);
+        *answer = Some(LiveAnswer {
+            selected: params.selected_answers,
+            time_taken: elapsed.as_micros() / 1000,
+        });
+        let _ = self.state.live_answers.insert(&key, answers);
+    }
+
+    async fn finalize_closed_live_quiz(&mut self, quiz_id: u64) {
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(
+            quiz_set.delivery_mode == DeliveryMode::Live,
+            "Quiz is not in live mode"
+        );
+        assert!(self.is_closed(&quiz_set), "Quiz has not ended yet");
+        self.auto_finalize_live_quiz(&mut quiz_set).await;
+    }
 
-            // 检查用户选择的答案是否与所有正确选项完全匹配（顺序无关）
-            let mut user_answers_sorted = user_answers.clone();
-            user_answers_sorted.sort();
-            let mut correct_options_sorted = question.correct_options.clone();
-            correct_options_sorted.sort();
+    /// 实时模式测验停止接受提交后，创建者没有结束最后一题时关闭作答并生成答题记录，
+    /// 否则参与者没有成绩，奖金和报名费会全部退还创建者。已生成过记录时不做任何事
+    async fn auto_finalize_live_quiz(&mut self, quiz_set: &mut QuizSet) {
+        if quiz_set.delivery_mode != DeliveryMode::Live || !self.is_closed(quiz_set) {
+            return;
+        }
+        if quiz_set.question_open {
+            quiz_set.question_open = false;
+            let _ = self.state.quiz_sets.insert(&quiz_set.id, quiz_set.clone());
+        }
+        self.finalize_live_quiz(quiz_set).await;
+    }
 
-            if user_answers_sorted == correct_options_sorted {
-                score += question.points;
+    /// 最后一题结束后，为每个实时模式参与者生成完整的答题记录
+    async fn finalize_live_quiz(&mut self, quiz_set: &QuizSet) {
+        let quiz_id = quiz_set.id;
+        let participants = self
+            .state
+            .live_participants
+            .get(&quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        for user in participants {
+            let key = (quiz_id, user.clone());
+            let live_answers = self
+                .state
+                .live_answers
+                .get(&key)
+                .await
+                .unwrap()
+                .unwrap_or_default();
+            let mut answers = Vec::new();
+            let mut per_question_times = Vec::new();
+            for answer in live_answers {
+                let answer = answer.unwrap_or(LiveAnswer {
+                    selected: Vec::new(),
+                    time_taken: 0,
+                });
+                answers.push(answer.selected);
+                per_question_times.push(answer.time_taken);
             }
+            let time_taken = per_question_times
+                .iter()
+                .fold(0u64, |total, &time| total.saturating_add(time));
+            self.record_attempt(quiz_set, user, answers, time_taken, per_question_times)
+                .await;
+            let _ = self.state.live_answers.remove(&key);
         }
+        let _ = self.state.live_participants.remove(&quiz_id);
+    }
+
+    /// 计分并保存答题记录，同时更新参与记录、累计成绩、团队、对战和排行榜
+    async fn record_attempt(
+        &mut self,
+        quiz_set: &QuizSet,
+        user: String,
+        answers: Vec<Vec<u32>>,
+        time_taken: u64,
+        per_question_times: Vec<u64>,
+    ) {
+        let quiz_id = quiz_set.id;
+        let total_points = quiz_set.total_points;
+
+        // 计算得分，答错的题目按 penalty 扣分，总分最低为0
+        let scored = quiz::score_answers(&quiz_set.questions, quiz_set.penalty, &answers)
+            .unwrap_or_else(|error| panic!("{error}"));
+        let score = scored.score;
 
         // 创建答题记录
         let attempt = UserAttempt {
             quiz_id,
             user: user.clone(),
-            answers: params.answers,
+            answers: scored.answers,
             score,
-            time_taken: params.time_taken,
-            completed_at: now,
+            time_taken,
+            completed_at: self.runtime.system_time(),
+            per_question_times,
+            total_points,
         };
 
         // 存储答题记录
@@ -221,10 +1035,49 @@ impl QuizContract {
             .state
             .user_attempts
             .insert(&(quiz_id, user.clone()), attempt.clone());
-        // 记录答题事件
-        self.state.quiz_events.push(attempt);
+        // 记录答题事件和动态
+        self.record_app_event(AppEvent {
+            kind: ActivityKind::AnswersSubmitted,
+            quiz_id,
+            user: user.clone(),
+            timestamp: attempt.completed_at,
+        });
+        let sequence = self.state.quiz_event_range.get_mut().push();
+        let _ = self.state.quiz_events.insert(&sequence, attempt);
 
-        // 记录用户参与
+        // 发放徽章
+        self.award_badge(&user, BadgeKind::FirstQuiz, quiz_id).await;
+        if score == total_points {
+            self.award_badge(&user, BadgeKind::PerfectScore, quiz_id)
+                .await;
+        }
+        let mut times = self
+            .state
+            .quiz_times
+            .get(&quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        let faster = times.partition_point(|&time| time < time_taken);
+        times.insert(faster, time_taken);
+        if times.len() >= 10 && faster < times.len() / 10 {
+            self.award_badge(&user, BadgeKind::SpeedDemon, quiz_id)
+                .await;
+        }
+        let _ = self.state.quiz_times.insert(&quiz_id, times);
+
+        // 记录测验参与用户
+        let mut participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        participants.push(user.clone());
+        let _ = self.state.quiz_participants.insert(&quiz_id, participants);
+
+        // 记录用户参与
         let mut participations = self
             .state
             .user_participations
@@ -235,40 +1088,864 @@ impl QuizContract {
         participations.push(quiz_id);
         let _ = self.state.user_participations.insert(&user, participations);
 
+        // 更新全局累计成绩
+        let mut global_score = self
+            .state
+            .global_scores
+            .get(&user)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        global_score.total_score = global_score.total_score.saturating_add(u64::from(score));
+        global_score.quizzes_played = global_score.quizzes_played.saturating_add(1);
+        global_score.total_time = global_score.total_time.saturating_add(time_taken);
+        let _ = self.state.global_scores.insert(&user, global_score);
+
+        // 累加到用户所属团队的总分
+        if let Some(team_id) = self
+            .state
+            .user_team
+            .get(&(quiz_id, user.clone()))
+            .await
+            .unwrap()
+        {
+            let mut team = self
+                .state
+                .quiz_teams
+                .get(&(quiz_id, team_id))
+                .await
+                .unwrap()
+                .expect("Team not found");
+            team.total_score = team.total_score.saturating_add(u64::from(score));
+            let _ = self.state.quiz_teams.insert(&(quiz_id, team_id), team);
+        }
+
+        // 双方都已提交时结算对战
+        self.resolve_duels(quiz_id, &user).await;
+
         // 更新排行榜
-        self.update_leaderboard(quiz_id, user, score).await;
+        self.update_leaderboard(quiz_id, user, score, time_taken, total_points)
+            .await;
     }
 
-    async fn update_leaderboard(&mut self, quiz_id: u64, user: String, score: u32) {
-        // 这里简单实现一个排行榜更新逻辑
-        // 实际项目中可能需要更复杂的排序和存储策略
-        let mut entries = self
+    async fn create_team(&mut self, params: CreateTeamParams) {
+        let quiz_set = self
             .state
-            .leaderboard
+            .quiz_sets
+            .get(&params.quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(
+            self.runtime.system_time() < quiz_set.start_time,
+            "Teams can only be created before the quiz starts"
+        );
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+
+        let name = params.name.trim().to_string();
+        assert!(
+            !name.is_empty() && name.chars().count() <= 32,
+            "Team name must be non-empty and at most 32 characters"
+        );
+
+        let team_count = self
+            .state
+            .team_counts
+            .get(&params.quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or(0);
+        for team_id in 0..team_count {
+            if let Some(team) = self
+                .state
+                .quiz_teams
+                .get(&(params.quiz_id, team_id))
+                .await
+                .unwrap()
+            {
+                assert!(team.name != name, "Team name already exists in this quiz");
+            }
+        }
+
+        let team = Team {
+            id: team_count,
+            name,
+            members: Vec::new(),
+            total_score: 0,
+        };
+        let _ = self
+            .state
+            .quiz_teams
+            .insert(&(params.quiz_id, team_count), team);
+        let next_count = team_count.checked_add(1).expect("Team ID overflow");
+        let _ = self.state.team_counts.insert(&params.quiz_id, next_count);
+    }
+
+    async fn join_team(&mut self, params: JoinTeamParams) {
+        let user = self.claim_nick_name(&params.nick_name).await;
+        let quiz_set = self
+            .state
+            .quiz_sets
+            .get(&params.quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(
+            self.runtime.system_time() < quiz_set.start_time,
+            "Teams can only be joined before the quiz starts"
+        );
+        assert!(!quiz_set.hidden, "Quiz is hidden");
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+
+        let key = (params.quiz_id, user.clone());
+        assert!(
+            !self.state.user_team.contains_key(&key).await.unwrap(),
+            "User has already joined a team in this quiz"
+        );
+        let mut team = self
+            .state
+            .quiz_teams
+            .get(&(params.quiz_id, params.team_id))
+            .await
+            .unwrap()
+            .expect("Team not found");
+        team.members.push(user);
+        let _ = self
+            .state
+            .quiz_teams
+            .insert(&(params.quiz_id, params.team_id), team);
+        let _ = self.state.user_team.insert(&key, params.team_id);
+    }
+
+    async fn rate_quiz(&mut self, params: RateQuizParams) {
+        let user = self.claim_nick_name(&params.nick_name).await;
+        assert!(
+            (1..=5).contains(&params.stars),
+            "Rating must be between 1 and 5 stars"
+        );
+        let comment = params
+            .comment
+            .map(|comment| comment.trim().to_string())
+            .filter(|comment| !comment.is_empty());
+        if let Some(comment) = &comment {
+            assert!(
+                comment.chars().count() <= MAX_RATING_COMMENT_CHARS,
+                "Comment must be at most {MAX_RATING_COMMENT_CHARS} characters"
+            );
+        }
+
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&params.quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(!quiz_set.hidden, "Quiz is hidden");
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+        let key = (params.quiz_id, user);
+        assert!(
+            self.state.user_attempts.contains_key(&key).await.unwrap(),
+            "User must attempt the quiz before rating it"
+        );
+
+        // 覆盖之前的评价时先从汇总中减去旧的星级
+        match self.state.quiz_ratings.get(&key).await.unwrap() {
+            Some(previous) => {
+                quiz_set.rating_total -= u64::from(previous.stars);
+            }
+            None => {
+                quiz_set.rating_count += 1;
+            }
+        }
+        quiz_set.rating_total += u64::from(params.stars);
+
+        let rating = Rating {
+            stars: params.stars,
+            comment,
+            rated_at: self.runtime.system_time(),
+        };
+        let _ = self.state.quiz_ratings.insert(&key, rating);
+        let _ = self.state.quiz_sets.insert(&params.quiz_id, quiz_set);
+    }
+
+    async fn distribute_prizes(&mut self, quiz_id: u64) {
+        let mut quiz_set = self
+            .state
+            .quiz_sets
             .get(&quiz_id)
             .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(self.is_closed(&quiz_set), "Quiz has not ended yet");
+        assert!(
+            !quiz_set.prize_distributed,
+            "Prizes have already been distributed"
+        );
+        assert!(quiz_set.pool_total > Amount::ZERO, "Quiz has no prize");
+        self.auto_finalize_live_quiz(&mut quiz_set).await;
+
+        let entries = self
+            .top_entries(&quiz_set, quiz_set.prize_split.len())
+            .await;
+        let mut winner_owners = Vec::new();
+        for entry in entries.into_iter().take(quiz_set.prize_split.len()) {
+            let owner = self
+                .state
+                .participant_owners
+                .get(&(quiz_id, entry.user))
+                .await
+                .expect("Failed to get participant owner");
+            winner_owners.push(owner);
+        }
+        let (payouts, refund) =
+            quiz::prize_payouts(quiz_set.pool_total, &quiz_set.prize_split, &winner_owners);
+        for (owner, share) in payouts {
+            self.pay_from_application(owner, share);
+        }
+        if refund > Amount::ZERO {
+            self.pay_from_application(quiz_set.creator_owner, refund);
+        }
+
+        quiz_set.prize_distributed = true;
+        let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set);
+    }
+
+    async fn refund_entries(&mut self, quiz_id: u64) {
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(
+            !quiz_set.prize_distributed,
+            "Prizes have already been distributed"
+        );
+        let now = self.runtime.system_time();
+        assert!(
+            quiz_set.can_be_refunded_by(&signer, self.state.config.get(), now),
+            "Only the creator before the quiz starts or an admin can refund entries"
+        );
+        self.refund_pool(&mut quiz_set).await;
+        let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set);
+    }
+
+    /// 向每位付款的参与者退还报名费，向创建者退还托管的奖金，并取消测验
+    async fn refund_pool(&mut self, quiz_set: &mut QuizSet) {
+        if quiz_set.entry_fee > Amount::ZERO {
+            let quiz_id = quiz_set.id;
+            // 付费参与者可能只在实时模式中作答过，尚未进入参与者列表
+            let mut users = self
+                .state
+                .quiz_participants
+                .get(&quiz_id)
+                .await
+                .expect("Failed to get quiz participants")
+                .unwrap_or_default();
+            let live_participants = self
+                .state
+                .live_participants
+                .get(&quiz_id)
+                .await
+                .expect("Failed to get live participants")
+                .unwrap_or_default();
+            for user in live_participants {
+                if !users.contains(&user) {
+                    users.push(user);
+                }
+            }
+            for user in users {
+                let payer = self
+                    .state
+                    .participant_owners
+                    .get(&(quiz_id, user))
+                    .await
+                    .expect("Failed to get participant owner");
+                if let Some(payer) = payer {
+                    self.pay_from_application(payer, quiz_set.entry_fee);
+                }
+            }
+        }
+        if quiz_set.prize > Amount::ZERO {
+            self.pay_from_application(quiz_set.creator_owner, quiz_set.prize);
+        }
+        quiz_set.cancel();
+    }
+
+    fn update_config(&mut self, config: AppConfig) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(
+            self.is_admin(&signer),
+            "Only an admin can update the config"
+        );
+        config.validate().unwrap_or_else(|error| panic!("{error}"));
+        self.state.config.set(config);
+    }
+
+    async fn report_quiz(&mut self, params: ReportQuizParams) {
+        let user = self.claim_nick_name(&params.nick_name).await;
+        let owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        let reason = params.reason.trim().to_string();
+        assert!(!reason.is_empty(), "Report reason cannot be empty");
+        assert!(
+            reason.chars().count() <= MAX_REPORT_REASON_CHARS,
+            "Report reason must be at most {MAX_REPORT_REASON_CHARS} characters"
+        );
+
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&params.quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(!quiz_set.hidden, "Quiz is hidden");
+        assert!(!quiz_set.cancelled, "Quiz has been cancelled");
+        let key = (params.quiz_id, owner);
+        assert!(
+            !self.state.quiz_reports.contains_key(&key).await.unwrap(),
+            "Account already has an open report for this quiz"
+        );
+
+        let report = Report {
+            user,
+            reason,
+            reported_at: self.runtime.system_time(),
+        };
+        let _ = self.state.quiz_reports.insert(&key, report);
+        quiz_set.report_count += 1;
+        let _ = self.state.quiz_sets.insert(&params.quiz_id, quiz_set);
+    }
+
+    async fn moderate_quiz(&mut self, params: ModerateQuizParams) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(self.is_admin(&signer), "Only an admin can moderate quizzes");
+        let quiz_id = params.quiz_id;
+        let mut quiz_set = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+
+        // 处理后关闭该测验的所有举报
+        self.clear_reports(quiz_id).await;
+        match params.action {
+            ModerationAction::Hide | ModerationAction::Restore => {
+                quiz_set.hidden = params.action == ModerationAction::Hide;
+                quiz_set.report_count = 0;
+                let _ = self.state.quiz_sets.insert(&quiz_id, quiz_set);
+            }
+            ModerationAction::Delete => {
+                // 未发放的奖金和报名费退还给付款人
+                if !quiz_set.prize_distributed {
+                    self.refund_pool(&mut quiz_set).await;
+                }
+                // 保留答题记录，参与者仍可查询自己的成绩
+                let participants = self
+                    .state
+                    .quiz_participants
+                    .get(&quiz_id)
+                    .await
+                    .expect("Failed to get quiz participants")
+                    .unwrap_or_default();
+                let _ = self.state.quiz_sets.remove(&quiz_id);
+                self.remove_created_quiz(&quiz_set).await;
+                self.clear_quiz_records(quiz_id, &participants).await;
+            }
+        }
+    }
+
+    /// 从创建者昵称和账户的已创建测验列表中移除测验
+    async fn remove_created_quiz(&mut self, quiz_set: &QuizSet) {
+        let mut created_quizzes = self
+            .state
+            .user_created_quizzes
+            .get(&quiz_set.creator)
+            .await
+            .expect("Failed to get created quizzes")
+            .unwrap_or_default();
+        created_quizzes.retain(|&id| id != quiz_set.id);
+        let _ = self
+            .state
+            .user_created_quizzes
+            .insert(&quiz_set.creator, created_quizzes);
+
+        let mut owner_quizzes = self
+            .state
+            .owner_created_quizzes
+            .get(&quiz_set.creator_owner)
+            .await
+            .expect("Failed to get created quizzes")
+            .unwrap_or_default();
+        owner_quizzes.retain(|&id| id != quiz_set.id);
+        let _ = self
+            .state
+            .owner_created_quizzes
+            .insert(&quiz_set.creator_owner, owner_quizzes);
+    }
+
+    /// 删除测验的所有举报
+    async fn clear_reports(&mut self, quiz_id: u64) {
+        let mut keys = Vec::new();
+        self.state
+            .quiz_reports
+            .for_each_index(|key| {
+                if key.0 == quiz_id {
+                    keys.push(key);
+                }
+                Ok(())
+            })
+            .await
+            .expect("Failed to read quiz reports");
+        for key in keys {
+            self.state
+                .quiz_reports
+                .remove(&key)
+                .expect("Failed to remove quiz report");
+        }
+    }
+
+    async fn create_duel(&mut self, params: CreateDuelParams) {
+        let challenger = self.claim_nick_name(&params.nick_name).await;
+        let opponent =
+            quiz::normalize_nick_name(&params.opponent).unwrap_or_else(|error| panic!("{error}"));
+        assert!(challenger != opponent, "Cannot challenge yourself");
+
+        let quiz_set = self
+            .state
+            .quiz_sets
+            .get(&params.quiz_id)
+            .await
+            .expect("Failed to get QuizSet")
+            .expect("QuizSet not found");
+        assert!(!self.is_ended(&quiz_set), "Quiz has ended");
+
+        let deadline = match params.deadline_millis {
+            Some(millis) => quiz::millis_to_timestamp(Some(millis), None, "deadline")
+                .unwrap_or_else(|error| panic!("{error}")),
+            None => quiz_set.end_time,
+        };
+        assert!(
+            deadline > self.runtime.system_time() && deadline <= quiz_set.end_time,
+            "Duel deadline must be in the future and no later than the quiz end time"
+        );
+
+        let duel_id = *self.state.next_duel_id.get();
+        let duel = Duel {
+            id: duel_id,
+            quiz_id: params.quiz_id,
+            challenger: challenger.clone(),
+            opponent: opponent.clone(),
+            deadline,
+            status: DuelStatus::Pending,
+            winner: None,
+        };
+        let _ = self.state.duels.insert(&duel_id, duel);
+        for user in [challenger, opponent] {
+            let mut duel_ids = self
+                .state
+                .user_duels
+                .get(&user)
+                .await
+                .unwrap()
+                .unwrap_or_default();
+            duel_ids.push(duel_id);
+            let _ = self.state.user_duels.insert(&user, duel_ids);
+        }
+        let next_id = duel_id.checked_add(1).expect("Duel ID overflow");
+        self.state.next_duel_id.set(next_id);
+    }
+
+    async fn accept_duel(&mut self, params: AcceptDuelParams) {
+        let mut duel = self
+            .state
+            .duels
+            .get(&params.duel_id)
+            .await
+            .expect("Failed to get duel")
+            .expect("Duel not found");
+        let user = self.claim_nick_name(&params.nick_name).await;
+        assert!(user == duel.opponent, "Not a duel participant");
+        match duel.status(self.runtime.system_time()) {
+            DuelStatus::Pending => {}
+            DuelStatus::Expired => panic!("Duel has expired"),
+            DuelStatus::Accepted => panic!("Duel already accepted"),
+            DuelStatus::Resolved => panic!("Duel already resolved"),
+        }
+        duel.status = DuelStatus::Accepted;
+        let _ = self.state.duels.insert(&params.duel_id, duel.clone());
+
+        // 接受前双方可能都已提交
+        self.resolve_duels(duel.quiz_id, &duel.opponent).await;
+    }
+
+    /// 结算用户在该测验上已接受的对战：分数高者胜，分数相同用时短者胜，都相同为平局
+    async fn resolve_duels(&mut self, quiz_id: u64, user: &str) {
+        let duel_ids = self
+            .state
+            .user_duels
+            .get(user)
+            .await
             .unwrap()
             .unwrap_or_default();
+        for duel_id in duel_ids {
+            let Some(mut duel) = self.state.duels.get(&duel_id).await.unwrap() else {
+                continue;
+            };
+            if duel.quiz_id != quiz_id || duel.status != DuelStatus::Accepted {
+                continue;
+            }
+            let challenger_attempt = self
+                .state
+                .user_attempts
+                .get(&(quiz_id, duel.challenger.clone()))
+                .await
+                .unwrap();
+            let opponent_attempt = self
+                .state
+                .user_attempts
+                .get(&(quiz_id, duel.opponent.clone()))
+                .await
+                .unwrap();
+            let (Some(challenger_attempt), Some(opponent_attempt)) =
+                (challenger_attempt, opponent_attempt)
+            else {
+                continue;
+            };
 
-        // 查找用户是否已有条目
-        let existing_index = entries.iter().position(|entry| entry.user == user);
+            let ordering = challenger_attempt.score.cmp(&opponent_attempt.score).then(
+                opponent_attempt
+                    .time_taken
+                    .cmp(&challenger_attempt.time_taken),
+            );
+            duel.winner = match ordering {
+                Ordering::Greater => Some(duel.challenger.clone()),
+                Ordering::Less => Some(duel.opponent.clone()),
+                Ordering::Equal => None,
+            };
+            duel.status = DuelStatus::Resolved;
+            let _ = self.state.duels.insert(&duel_id, duel);
+        }
+    }
 
-        if let Some(index) = existing_index {
-            // 更新现有条目
-            entries[index].score = score;
-        } else {
-            // 添加新条目
-            entries.push(LeaderboardEntry {
-                user,
-                score,
-                time_taken: 0, // 这里可以从attempt中获取time_taken
-            });
+    /// 验证测验时间范围：开始时间在未来，结束时间晚于开始时间，且跨度不超过100年
+    fn validate_time_range(&mut self, start_time: Timestamp, end_time: Timestamp) {
+        assert!(
+            start_time > self.runtime.system_time(),
+            "Start time must be in the future"
+        );
+        assert!(end_time > start_time, "End time must be after start time");
+        assert!(
+            end_time.delta_since(start_time) <= TimeDelta::from_secs(3600 * 24 * 365 * 100),
+            "Time range is too long (maximum 100 years)"
+        );
+    }
+
+    /// 发放徽章。首次完成和首次创建每个用户只发放一次，其他徽章每个测验只发放一次
+    async fn award_badge(&mut self, user: &str, kind: BadgeKind, quiz_id: u64) {
+        let mut badges = self
+            .state
+            .user_badges
+            .get(user)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        let once_per_user = matches!(kind, BadgeKind::FirstQuiz | BadgeKind::Creator);
+        if badges
+            .iter()
+            .any(|badge| badge.kind == kind && (once_per_user || badge.quiz_id == quiz_id))
+        {
+            return;
         }
 
-        // 按分数排序（从高到低）
-        entries.sort_by(|a, b| b.score.cmp(&a.score));
+        let now = self.runtime.system_time();
+        badges.push(Badge {
+            kind,
+            quiz_id,
+            awarded_at: now,
+        });
+        let _ = self.state.user_badges.insert(user, badges);
+        self.record_app_event(AppEvent {
+            kind: ActivityKind::BadgeAwarded,
+            quiz_id,
+            user: user.to_string(),
+            timestamp: now,
+        });
+    }
+
+    /// 以下一个序号追加应用动态
+    fn record_app_event(&mut self, event: AppEvent) {
+        let sequence = self.state.app_event_range.get_mut().push();
+        let _ = self.state.app_events.insert(&sequence, event);
+    }
+
+    /// 删除早于截止时间的答题事件和应用动态。事件按时间顺序分配序号，
+    /// 因此从最早的序号开始删除，遇到不早于截止时间的事件即停止
+    async fn prune_events_before(&mut self, cutoff_millis: u64) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        assert!(self.is_admin(&signer), "Only an admin can prune events");
+        let cutoff = quiz::millis_to_timestamp(Some(cutoff_millis), None, "cutoff")
+            .unwrap_or_else(|error| panic!("{error}"));
+
+        let mut range = *self.state.quiz_event_range.get();
+        while range.start < range.end {
+            let event = self
+                .state
+                .quiz_events
+                .get(&range.start)
+                .await
+                .expect("Failed to get quiz event");
+            if event.is_some_and(|attempt| attempt.completed_at >= cutoff) {
+                break;
+            }
+            self.state
+                .quiz_events
+                .remove(&range.start)
+                .expect("Failed to remove quiz event");
+            range.start += 1;
+        }
+        self.state.quiz_event_range.set(range);
+
+        let mut range = *self.state.app_event_range.get();
+        while range.start < range.end {
+            let event = self
+                .state
+                .app_events
+                .get(&range.start)
+                .await
+                .expect("Failed to get app event");
+            if event.is_some_and(|event| event.timestamp >= cutoff) {
+                break;
+            }
+            self.state
+                .app_events
+                .remove(&range.start)
+                .expect("Failed to remove app event");
+            range.start += 1;
+        }
+        self.state.app_event_range.set(range);
+    }
+
+    /// 判断测验是否已经结束（当前时间超过结束时间）
+    fn is_ended(&mut self, quiz_set: &QuizSet) -> bool {
+        quiz_set.is_ended(self.runtime.system_time())
+    }
+
+    /// 判断测验是否已停止接受提交（当前时间超过结束时间加宽限时间）
+    fn is_closed(&mut self, quiz_set: &QuizSet) -> bool {
+        quiz_set.is_closed(self.runtime.system_time())
+    }
+
+    /// 当前链上应用自身的账户，用于托管奖金
+    fn application_account(&mut self) -> Account {
+        Account {
+            chain_id: self.runtime.chain_id(),
+            owner: self.runtime.application_id().forget_abi().into(),
+        }
+    }
+
+    /// 从应用账户向当前链上的账户转账
+    fn pay_from_application(&mut self, owner: AccountOwner, amount: Amount) {
+        let source = self.application_account().owner;
+        let destination = Account {
+            chain_id: self.runtime.chain_id(),
+            owner,
+        };
+        self.runtime.transfer(source, destination, amount);
+    }
+
+    /// 收费测验在首次提交时从参与者账户收取报名费并计入奖池。
+    /// 转账与状态更新在同一操作中执行，操作失败时一并回滚
+    fn collect_entry_fee(&mut self, quiz_set: &mut QuizSet) {
+        if quiz_set.entry_fee == Amount::ZERO {
+            return;
+        }
+        quiz_set.add_entry_fee();
+        let payer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Paying the entry fee requires an authenticated signer");
+        let destination = self.application_account();
+        self.runtime
+            .transfer(payer, destination, quiz_set.entry_fee);
+        let _ = self.state.quiz_sets.insert(&quiz_set.id, quiz_set.clone());
+    }
+
+    /// 规范化操作者的昵称，并检查昵称属于签名账户。昵称首次使用时绑定到签名账户，
+    /// 之后其他账户不能再以此昵称提交答案、评价、举报或参与团队和对战
+    async fn claim_nick_name(&mut self, nick_name: &str) -> String {
+        let nick_name =
+            quiz::normalize_nick_name(nick_name).unwrap_or_else(|error| panic!("{error}"));
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Failed to get authenticated signer: no user authenticated");
+        match self
+            .state
+            .nickname_owners
+            .get(&nick_name)
+            .await
+            .expect("Failed to get nickname owner")
+        {
+            Some(owner) => assert!(owner == signer, "Nickname belongs to another account"),
+            None => {
+                let _ = self.state.nickname_owners.insert(&nick_name, signer);
+            }
+        }
+        nick_name
+    }
+
+    /// 记录参与者提交答案时的签名账户
+    fn record_participant_owner(&mut self, quiz_id: u64, user: &str) {
+        if let Some(owner) = self.runtime.authenticated_signer() {
+            let _ = self
+                .state
+                .participant_owners
+                .insert(&(quiz_id, user.to_string()), owner);
+        }
+    }
+
+    /// 检查问题数量和选项数量不超过应用配置的上限
+    fn check_question_limits(&self, question_count: usize, max_option_count: usize) {
+        let config = self.state.config.get();
+        assert!(
+            question_count <= config.max_questions_per_quiz as usize,
+            "Too many questions (maximum {})",
+            config.max_questions_per_quiz
+        );
+        assert!(
+            max_option_count <= config.max_options_per_question as usize,
+            "Too many options in a question (maximum {})",
+            config.max_options_per_question
+        );
+    }
+
+    /// 判断账户是否为应用管理员
+    fn is_admin(&self, owner: &AccountOwner) -> bool {
+        self.state.config.get().is_admin(owner)
+    }
+
+    /// 测验的前 count 名。缓存的排行榜按当前配置截断，不足 count 条且参与者更多时
+    /// 扫描全部答题记录重新排序
+    async fn top_entries(&mut self, quiz_set: &QuizSet, count: usize) -> Vec<LeaderboardEntry> {
+        let quiz_id = quiz_set.id;
+        let leaderboard_size = self.state.config.get().leaderboard_size as usize;
+        let mut entries = self
+            .state
+            .leaderboard
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get leaderboard")
+            .unwrap_or_default();
+        entries.truncate(leaderboard_size);
+        let participant_count = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await
+            .expect("Failed to get quiz participants")
+            .map_or(0, |participants| participants.len());
+        if !quiz::cached_leaderboard_covers(entries.len(), participant_count, count) {
+            let mut attempts = Vec::new();
+            self.state
+                .user_attempts
+                .for_each_index_value(|(q_id, _), attempt| {
+                    if q_id == quiz_id {
+                        attempts.push(attempt.into_owned());
+                    }
+                    Ok(())
+                })
+                .await
+                .expect("Failed to read user attempts");
+            entries = quiz::rank_attempts(attempts)
+                .iter()
+                .map(LeaderboardEntry::from)
+                .collect();
+        }
+        entries.truncate(count);
+        entries
+    }
+
+    /// 测验不允许创建者参与时，拒绝创建者签名账户的提交
+    fn check_creator_participation(&mut self, quiz_set: &QuizSet) {
+        let signer = self.runtime.authenticated_signer();
+        assert!(
+            quiz_set.allows_participant(signer.as_ref()),
+            "Quiz creators cannot participate in their own quiz"
+        );
+    }
+
+    /// 更新测验的前N名排行榜，按分数降序、用时升序排列
+    async fn update_leaderboard(
+        &mut self,
+        quiz_id: u64,
+        user: String,
+        score: u32,
+        time_taken: u64,
+        total_points: u32,
+    ) {
+        let mut entries = self
+            .state
+            .leaderboard
+            .get(&quiz_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        // 替换用户已有的条目，按分数降序、用时升序排序，只保留前N名
+        let entry = LeaderboardEntry {
+            user,
+            score,
+            time_taken,
+            score_percent: quiz::score_percent(score, total_points),
+        };
+        let leaderboard_size = self.state.config.get().leaderboard_size as usize;
+        quiz::update_leaderboard_entries(&mut entries, entry, leaderboard_size);
 
         // 保存更新后的排行榜
         let _ = self.state.leaderboard.insert(&quiz_id, entries);
     }
 }
+
+/// 检查问题的正确选项，以及图片、选项图片和媒体URL，选项图片数量必须与选项数量一致
+fn validate_question(question: &QuestionParams) {
+    quiz::validate_correct_options(question).unwrap_or_else(|error| panic!("{error}"));
+    if let Some(image_url) = &question.image_url {
+        quiz::validate_url(image_url, "Question image URL")
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
+    if let Some(option_images) = &question.option_images {
+        assert_eq!(
+            option_images.len(),
+            question.options.len(),
+            "Option images must match the number of options"
+        );
+        for image_url in option_images.iter().flatten() {
+            quiz::validate_url(image_url, "Option image URL")
+                .unwrap_or_else(|error| panic!("{error}"));
+        }
+    }
+    if let Some(media_url) = &question.media_url {
+        quiz::validate_url(media_url, "Question media URL")
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
+}
@@ -3,10 +3,12 @@
 
 /*! ABI of the Quiz Application */
 
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{Enum, InputObject, SimpleObject};
 use linera_sdk::graphql::GraphQLMutationRoot;
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ContractAbi, ServiceAbi, Timestamp};
 use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
 
 pub mod state;
 
@@ -18,13 +20,285 @@ pub struct CreateQuizParams {
     pub title: String,
     pub description: String,
     pub questions: Vec<QuestionParams>,
-    pub time_limit: u64,    // 秒
-    pub start_time: String, // 毫秒时间戳字符串
-    pub end_time: String,   // 毫秒时间戳字符串
+    pub time_limit: u64,            // 秒
+    pub start_time: Option<String>, // 毫秒时间戳字符串，已弃用，请使用 start_time_millis
+    pub end_time: Option<String>,   // 毫秒时间戳字符串，已弃用，请使用 end_time_millis
     pub nick_name: String,
+    /// 分类标签，最多5个
+    #[graphql(default)]
+    pub tags: Vec<String>,
+    /// 参与人数上限，为空表示不限制
+    pub max_participants: Option<u32>,
+    /// 开始时间（毫秒时间戳）
+    pub start_time_millis: Option<u64>,
+    /// 结束时间（毫秒时间戳）
+    pub end_time_millis: Option<u64>,
+    /// 是否允许其他用户以此测验为模板创建新测验
+    #[graphql(default)]
+    pub allow_cloning: bool,
+    /// 引用题库中的问题ID，创建时复制到测验中，排在 questions 之后
+    #[graphql(default)]
+    pub bank_question_ids: Vec<String>,
+    /// 封面图片URL，必须为 http(s) 地址
+    pub image_url: Option<String>,
+    /// 每答错一题扣除的分数，未作答不扣分，总分最低为0
+    pub penalty: Option<u32>,
+    /// 团队排行榜的排名依据
+    #[graphql(default)]
+    pub team_ranking: TeamRanking,
+    /// 题目发放方式
+    #[graphql(default)]
+    pub delivery_mode: DeliveryMode,
+    /// 结束后仍接受提交的宽限时间（秒），最长24小时
+    #[graphql(default)]
+    pub grace_period_secs: u64,
+    /// 是否允许创建者参与自己的测验
+    #[graphql(default)]
+    pub allow_creator_participation: bool,
+    /// 奖金，创建时从创建者账户转入应用账户托管
+    pub prize: Option<Amount>,
+    /// 前几名分得奖金的百分比，最多3名且总和为100
+    #[graphql(default)]
+    pub prize_split: Vec<u8>,
+    /// 报名费，首次提交答案时从参与者账户收取并计入奖池
+    pub entry_fee: Option<Amount>,
 }
 
-/// 问题参数
+/// 题目发放方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum DeliveryMode {
+    /// 开始后一次性发放全部题目
+    #[default]
+    AllAtOnce,
+    /// 由创建者逐题发放，参与者逐题作答
+    Live,
+}
+
+/// 实时模式下提交单题答案的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct SubmitLiveAnswerParams {
+    pub quiz_id: u64,
+    pub question_id: u32,
+    pub selected_answers: Vec<u32>,
+    pub nick_name: String,
+}
+
+/// 团队排行榜的排名依据
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum TeamRanking {
+    /// 按团队总分
+    #[default]
+    TotalScore,
+    /// 按成员平均分，避免人数多的团队占优
+    AverageScore,
+}
+
+/// 创建团队的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct CreateTeamParams {
+    pub quiz_id: u64,
+    pub name: String,
+}
+
+/// 加入团队的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct JoinTeamParams {
+    pub quiz_id: u64,
+    pub team_id: u32,
+    pub nick_name: String,
+}
+
+/// 评价测验的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct RateQuizParams {
+    pub quiz_id: u64,
+    pub stars: u8, // 1-5星
+    pub comment: Option<String>,
+    pub nick_name: String,
+}
+
+/// 评论的最大长度（字符数）
+pub const MAX_RATING_COMMENT_CHARS: usize = 500;
+
+/// 举报测验的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct ReportQuizParams {
+    pub quiz_id: u64,
+    pub reason: String,
+    pub nick_name: String,
+}
+
+/// 举报理由的最大长度（字符数）
+pub const MAX_REPORT_REASON_CHARS: usize = 500;
+
+/// 管理员处理测验的方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ModerationAction {
+    /// 从列表中隐藏并停止接受提交
+    Hide,
+    /// 恢复被隐藏的测验
+    Restore,
+    /// 删除测验及其参与、排行榜、团队、对战和评价记录，保留参与者的答题记录
+    Delete,
+}
+
+/// 管理员处理测验的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct ModerateQuizParams {
+    pub quiz_id: u64,
+    pub action: ModerationAction,
+}
+
+/// 未处理的举报
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct ReportView {
+    pub quiz_id: u64,
+    pub owner: AccountOwner, // 举报账户
+    pub user: String,
+    pub reason: String,
+    pub reported_at: String, // 微秒时间戳字符串
+}
+
+/// 团队排行榜条目
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct TeamLeaderboardEntry {
+    pub team_id: u32,
+    pub name: String,
+    pub member_count: u32,
+    pub total_score: u64,
+    pub average_score: f64, // 按全部成员计算，未作答的成员计0分
+}
+
+/// 添加或更新题库问题的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct BankQuestionParams {
+    pub bank_question_id: String,
+    pub question: QuestionParams,
+}
+
+/// 以已有测验为模板创建新测验的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct CloneQuizParams {
+    pub source_quiz_id: u64,
+    pub title: String,
+    pub start_time_millis: u64, // 毫秒时间戳
+    pub end_time_millis: u64,   // 毫秒时间戳
+    pub nick_name: String,
+}
+
+/// 导入测验的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct ImportQuizParams {
+    pub payload: String,        // export_quiz 导出的JSON
+    pub start_time_millis: u64, // 毫秒时间戳
+    pub end_time_millis: u64,   // 毫秒时间戳
+    pub nick_name: String,
+}
+
+/// 当前的测验导出格式版本
+pub const QUIZ_EXPORT_VERSION: u32 = 1;
+
+/// 当前的用户数据导出格式版本
+pub const USER_DATA_EXPORT_VERSION: u32 = 1;
+
+/// 用户数据导出每页包含的答题记录数量
+pub const USER_DATA_EXPORT_PAGE_SIZE: usize = 50;
+
+/// 用户数据导出，答题记录按参与顺序分页
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserDataExport {
+    pub version: u32,
+    pub user: String,
+    pub page: u32,
+    pub has_more: bool,
+    pub participations: Vec<u64>,
+    pub created_quiz_ids: Vec<u64>,
+    pub attempts: Vec<UserAttemptView>,
+}
+
+/// 导入测验JSON的最大字节数
+pub const MAX_QUIZ_EXPORT_SIZE: usize = 256 * 1024;
+
+/// 可在不同部署之间迁移的测验定义，不包含ID和时间
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizExport {
+    pub version: u32,
+    pub title: String,
+    pub description: String,
+    pub questions: Vec<QuestionParams>,
+    pub time_limit: u64, // 秒
+    pub tags: Vec<String>,
+    pub max_participants: Option<u32>,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub penalty: Option<u32>,
+    #[serde(default)]
+    pub team_ranking: TeamRanking,
+    #[serde(default)]
+    pub delivery_mode: DeliveryMode,
+    #[serde(default)]
+    pub grace_period_secs: u64,
+    #[serde(default)]
+    pub allow_creator_participation: bool,
+}
+
+impl From<&state::QuizSet> for QuizExport {
+    fn from(quiz: &state::QuizSet) -> Self {
+        QuizExport {
+            version: QUIZ_EXPORT_VERSION,
+            title: quiz.title.clone(),
+            description: quiz.description.clone(),
+            questions: quiz
+                .questions
+                .iter()
+                .map(|q| QuestionParams {
+                    text: q.text.clone(),
+                    options: q.options.clone(),
+                    correct_options: q.correct_options.clone(),
+                    points: q.points,
+                    image_url: q.image_url.clone(),
+                    option_images: q.option_images.clone(),
+                    media_url: q.media_url.clone(),
+                    single_choice: Some(q.single_choice),
+                })
+                .collect(),
+            time_limit: quiz.time_limit,
+            tags: quiz.tags.clone(),
+            max_participants: quiz.max_participants,
+            image_url: quiz.image_url.clone(),
+            penalty: quiz.penalty,
+            team_ranking: quiz.team_ranking,
+            delivery_mode: quiz.delivery_mode,
+            grace_period_secs: quiz.grace_period_secs,
+            allow_creator_participation: quiz.allow_creator_participation,
+        }
+    }
+}
+
+impl QuizExport {
+    /// 解析导入的测验JSON：先检查大小，再解析并检查格式版本
+    pub fn from_json(payload: &str) -> Result<Self, String> {
+        if payload.len() > MAX_QUIZ_EXPORT_SIZE {
+            return Err("Quiz payload is too large (maximum 256 KB)".to_string());
+        }
+        let export: QuizExport = serde_json::from_str(payload)
+            .map_err(|error| format!("Invalid quiz payload: {error}"))?;
+        if export.version != QUIZ_EXPORT_VERSION {
+            return Err("Unsupported quiz payload version".to_string());
+        }
+        Ok(export)
+    }
+}
+
+/// 题库问题视图
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct BankQuestionView {
+    pub id: String,
+    pub question: QuestionParams,
+}
+
+/// 问题参数。serde(default) 只对导入的JSON生效，题库中以BCS存储的问题不受影响
 #[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, InputObject)]
 #[graphql(input_name = "QuestionParamsInput")]
 pub struct QuestionParams {
@@ -32,6 +306,18 @@ pub struct QuestionParams {
     pub options: Vec<String>,
     pub correct_options: Vec<u32>,
     pub points: u32,
+    /// 问题图片URL
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// 与 options 一一对应的选项图片URL
+    #[serde(default)]
+    pub option_images: Option<Vec<Option<String>>>,
+    /// 问题配套的图片或音频URL
+    #[serde(default)]
+    pub media_url: Option<String>,
+    /// 是否为单选题，单选题只能选择一个选项，未提供时为多选题
+    #[serde(default)]
+    pub single_choice: Option<bool>,
 }
 
 /// 提交答案的参数
@@ -41,14 +327,145 @@ pub struct SubmitAnswersParams {
     pub answers: Vec<Vec<u32>>, // 每个问题的答案选项索引列表，支持多选
     pub time_taken: u64,        // 毫秒
     pub nick_name: String,
+    /// 答案是否按 quiz_set_for_user 返回的乱序题目和选项顺序提交
+    #[graphql(default)]
+    pub shuffled: bool,
+    /// 每个问题的作答用时（毫秒），顺序与 answers 一致，总和不能超过 time_taken
+    #[graphql(default)]
+    pub per_question_times: Vec<u64>,
+}
+
+/// 单个问题排行榜条目
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct QuestionLeaderboardEntry {
+    pub user: String,
+    pub time_taken: u64, // 毫秒，该问题的作答用时，未提供时为整个测验的用时
 }
 
 /// 排行榜条目
-#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone, PartialEq)]
 pub struct LeaderboardEntry {
     pub user: String,
     pub score: u32,
     pub time_taken: u64,
+    pub score_percent: f64, // 得分占测验总分的百分比
+}
+
+impl From<&state::UserAttempt> for LeaderboardEntry {
+    fn from(attempt: &state::UserAttempt) -> Self {
+        LeaderboardEntry {
+            user: attempt.user.clone(),
+            score: attempt.score,
+            time_taken: attempt.time_taken,
+            score_percent: score_percent(attempt.score, attempt.total_points),
+        }
+    }
+}
+
+/// 排行榜排序键：分数降序、用时升序，都相同时按用户名升序，
+/// 保证缓存的排行榜与扫描答题记录得到的顺序一致
+pub fn leaderboard_key(score: u32, time_taken: u64, user: &str) -> (Reverse<u32>, u64, &str) {
+    (Reverse(score), time_taken, user)
+}
+
+/// 缓存的排行榜能否给出前 limit 名。缓存按写入时的排行榜大小截断，
+/// 配置调大后缓存可能少于 limit 条，而测验的参与者更多，此时需要扫描答题记录
+pub fn cached_leaderboard_covers(
+    cached_len: usize,
+    participant_count: usize,
+    limit: usize,
+) -> bool {
+    cached_len >= limit || cached_len >= participant_count
+}
+
+/// 更新缓存的排行榜：替换用户已有的条目，重新排序并只保留前 size 名
+pub fn update_leaderboard_entries(
+    entries: &mut Vec<LeaderboardEntry>,
+    entry: LeaderboardEntry,
+    size: usize,
+) {
+    entries.retain(|existing| existing.user != entry.user);
+    entries.push(entry);
+    entries.sort_by(|a, b| {
+        leaderboard_key(a.score, a.time_taken, &a.user).cmp(&leaderboard_key(
+            b.score,
+            b.time_taken,
+            &b.user,
+        ))
+    });
+    entries.truncate(size);
+}
+
+/// 按排行榜顺序排列测验的答题记录，用于超出缓存范围时扫描计算排行榜
+pub fn rank_attempts(mut attempts: Vec<state::UserAttempt>) -> Vec<state::UserAttempt> {
+    attempts.sort_by(|a, b| {
+        leaderboard_key(a.score, a.time_taken, &a.user).cmp(&leaderboard_key(
+            b.score,
+            b.time_taken,
+            &b.user,
+        ))
+    });
+    attempts
+}
+
+/// 全局排行榜条目
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct GlobalLeaderboardEntry {
+    pub user: String,
+    pub total_score: u64,
+    pub quizzes_played: u32,
+    pub average_time: u64, // 毫秒
+}
+
+/// 用户在单个测验中的排名
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct QuizRankView {
+    pub rank: u32, // 成绩相同的用户名次相同
+    pub total_participants: u32,
+    pub score: u32,
+    pub time_taken: u64,
+}
+
+/// 单个问题的答题统计，测验停止接受提交前不公开答对人数、选项分布和平均得分
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct QuestionStatsView {
+    pub question_id: u32,
+    pub attempts: u32,
+    pub correct_count: Option<u32>,
+    pub option_counts: Option<Vec<u32>>, // 每个选项被选择的次数
+    pub average_points: Option<f64>,
+}
+
+/// 用户在单个测验中的成绩和排名
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct UserQuizResult {
+    pub score: u32,
+    pub time_taken: u64,
+    pub rank: u32,
+    pub percentile: f64,
+    pub total_participants: u32,
+}
+
+/// 测验汇总统计
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct QuizSummaryView {
+    pub quiz_id: u64,
+    pub participant_count: u32,
+    pub average_score: f64,
+    pub highest_score: u32,
+    pub median_time_taken: u64, // 毫秒，偶数个时取较小的中间值
+    pub mean_time_taken: u64,   // 毫秒
+}
+
+/// 归档测验的精简摘要，归档后答题记录会被删除
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+pub struct ArchivedQuizSummary {
+    pub quiz_id: u64,
+    pub title: String,
+    pub creator: String,
+    pub participant_count: u32,
+    pub top_entries: Vec<LeaderboardEntry>, // 前三名
+    pub end_time_micros: u64,
 }
 
 /// 应用支持的操作
@@ -58,27 +475,50 @@ pub enum Operation {
     CreateQuiz(CreateQuizParams),
     /// 提交Quiz答案
     SubmitAnswers(SubmitAnswersParams),
-}
-
-/// 应用支持的查询
-#[derive(Debug, Serialize, Deserialize)]
-pub enum Query {
-    /// 获取所有Quiz集合
-    GetQuizSets,
-    /// 获取Quiz集合详情
-    GetQuizSet(u64),
-    /// 获取用户的Quiz尝试记录
-    GetUserAttempts(String),
-    /// 获取Quiz排行榜
-    GetLeaderboard,
-    /// 获取单个Quiz的排行榜
-    GetQuizLeaderboard(u64),
-    /// 获取用户参与的测验集合
-    GetUserParticipations(String),
-    /// 获取用户创建的测验集合
-    GetUserCreatedQuizzes(String),
-    /// 获取用户参与的测验集合详情
-    GetUserParticipatedQuizzes(String),
+    /// 以已有测验为模板创建新测验，需要是原测验创建者或原测验允许复制，且原测验已结束、未被隐藏
+    CloneQuiz(CloneQuizParams),
+    /// 从 export_quiz 导出的JSON创建新测验
+    ImportQuiz(ImportQuizParams),
+    /// 向当前签名账户的题库添加问题
+    AddBankQuestion(BankQuestionParams),
+    /// 更新题库中的问题，不影响已创建的测验
+    UpdateBankQuestion(BankQuestionParams),
+    /// 从题库中删除问题
+    DeleteBankQuestion(String),
+    /// 归档已结束足够久的测验，只保留摘要，需要是测验创建者或管理员
+    ArchiveQuiz(u64),
+    /// 向其他用户发起同一测验的对战
+    CreateDuel(CreateDuelParams),
+    /// 对手接受对战
+    AcceptDuel(AcceptDuelParams),
+    /// 在测验开始前创建团队
+    CreateTeam(CreateTeamParams),
+    /// 在测验开始前加入团队，每个用户只能加入一个团队
+    JoinTeam(JoinTeamParams),
+    /// 实时模式下发放下一题，仅限测验创建者
+    RevealNextQuestion(u64),
+    /// 实时模式下结束当前题目的作答，结束最后一题时生成所有参与者的答题记录
+    CloseCurrentQuestion(u64),
+    /// 实时模式下提交当前题目的答案
+    SubmitLiveAnswer(SubmitLiveAnswerParams),
+    /// 作答后为测验评分并留下评论，再次评价会覆盖之前的评价
+    RateQuiz(RateQuizParams),
+    /// 举报不当测验，每个账户对同一测验只能有一条未处理的举报
+    ReportQuiz(ReportQuizParams),
+    /// 隐藏、恢复或删除测验并处理其举报，仅限管理员
+    ModerateQuiz(ModerateQuizParams),
+    /// 替换应用配置，仅限管理员
+    UpdateConfig(AppConfig),
+    /// 测验结束后按排行榜发放奖金，任何人都可以调用。
+    /// 没有获奖者的名次和没有签名账户的获奖者的奖金退还创建者
+    DistributePrizes(u64),
+    /// 取消测验并退还报名费和创建者托管的奖金。创建者只能在测验开始前调用，
+    /// 管理员在奖金发放前随时可以调用；取消后测验不再接受提交
+    RefundEntries(u64),
+    /// 实时模式测验停止接受提交后，为创建者未结束的测验生成答题记录，任何人都可以调用
+    FinalizeLiveQuiz(u64),
+    /// 删除早于指定毫秒时间戳的答题事件和应用动态，仅限管理员
+    PruneEventsBefore(u64),
 }
 
 /// 用户答题尝试视图
@@ -90,6 +530,34 @@ pub struct UserAttemptView {
     pub score: u32,
     pub time_taken: u64,
     pub completed_at: String, // 微秒时间戳字符串
+    pub per_question_times: Vec<u64>,
+    pub total_points: u32,
+    pub score_percent: f64, // 得分占测验总分的百分比，总分为0时为0
+}
+
+impl From<&state::UserAttempt> for UserAttemptView {
+    fn from(attempt: &state::UserAttempt) -> Self {
+        UserAttemptView {
+            quiz_id: attempt.quiz_id,
+            user: attempt.user.clone(),
+            answers: attempt.answers.clone(),
+            score: attempt.score,
+            time_taken: attempt.time_taken,
+            completed_at: attempt.completed_at.micros().to_string(),
+            per_question_times: attempt.per_question_times.clone(),
+            total_points: attempt.total_points,
+            score_percent: score_percent(attempt.score, attempt.total_points),
+        }
+    }
+}
+
+/// 计算得分百分比，总分为0时返回0
+pub fn score_percent(score: u32, total_points: u32) -> f64 {
+    if total_points == 0 {
+        0.0
+    } else {
+        f64::from(score) * 100.0 / f64::from(total_points)
+    }
 }
 
 /// 测验尝试记录
@@ -99,6 +567,240 @@ pub struct QuizAttempt {
     pub attempt: UserAttemptView,
 }
 
+/// 发起对战的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct CreateDuelParams {
+    pub quiz_id: u64,
+    pub opponent: String,             // 对手昵称
+    pub deadline_millis: Option<u64>, // 接受对战的截止时间（毫秒时间戳），默认为测验结束时间
+    pub nick_name: String,
+}
+
+/// 接受对战的参数
+#[derive(Debug, Serialize, Deserialize, InputObject)]
+pub struct AcceptDuelParams {
+    pub duel_id: u64,
+    pub nick_name: String,
+}
+
+/// 对战状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum DuelStatus {
+    /// 等待对手接受
+    Pending,
+    /// 已接受，等待双方提交答案
+    Accepted,
+    /// 双方均已提交，已决出结果
+    Resolved,
+    /// 对手未在截止时间前接受
+    Expired,
+}
+
+/// 对战视图
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct DuelView {
+    pub id: u64,
+    pub quiz_id: u64,
+    pub challenger: String,
+    pub opponent: String,
+    pub deadline_micros: u64,
+    pub status: DuelStatus,
+    pub winner: Option<String>, // 已决出结果且非平局时为胜者昵称
+}
+
+impl DuelView {
+    /// 由存储的对战构建视图，超过截止时间仍未接受的对战显示为已过期
+    pub fn new(duel: &state::Duel, now: Timestamp) -> Self {
+        DuelView {
+            id: duel.id,
+            quiz_id: duel.quiz_id,
+            challenger: duel.challenger.clone(),
+            opponent: duel.opponent.clone(),
+            deadline_micros: duel.deadline.micros(),
+            status: duel.status(now),
+            winner: duel.winner.clone(),
+        }
+    }
+}
+
+/// 动态类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ActivityKind {
+    /// 创建了测验
+    QuizCreated,
+    /// 提交了答案
+    AnswersSubmitted,
+    /// 获得了徽章
+    BadgeAwarded,
+}
+
+/// 徽章类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Enum)]
+pub enum BadgeKind {
+    /// 首次完成测验
+    FirstQuiz,
+    /// 测验满分
+    PerfectScore,
+    /// 用时进入测验前10%（至少10人参与时）
+    SpeedDemon,
+    /// 首次创建测验
+    Creator,
+}
+
+/// 徽章视图
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct BadgeView {
+    pub kind: BadgeKind,
+    pub quiz_id: u64,
+    pub awarded_at: String, // 微秒时间戳字符串
+}
+
+impl From<&state::Badge> for BadgeView {
+    fn from(badge: &state::Badge) -> Self {
+        BadgeView {
+            kind: badge.kind,
+            quiz_id: badge.quiz_id,
+            awarded_at: badge.awarded_at.micros().to_string(),
+        }
+    }
+}
+
+/// 测验评价视图
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct RatingView {
+    pub user: String,
+    pub stars: u8,
+    pub comment: Option<String>,
+    pub rated_at: String, // 微秒时间戳字符串
+}
+
+/// 各类徽章的获得人数
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct BadgeCount {
+    pub kind: BadgeKind,
+    pub count: u32,
+}
+
+/// 动态视图
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct ActivityView {
+    pub kind: ActivityKind,
+    pub quiz_id: u64,
+    pub user: String,
+    pub timestamp: String, // 微秒时间戳字符串
+}
+
+impl From<&state::AppEvent> for ActivityView {
+    fn from(event: &state::AppEvent) -> Self {
+        ActivityView {
+            kind: event.kind,
+            quiz_id: event.quiz_id,
+            user: event.user.clone(),
+            timestamp: event.timestamp.micros().to_string(),
+        }
+    }
+}
+
+/// 动态列表一次最多返回的条数
+pub const MAX_ACTIVITY_LIMIT: u32 = 100;
+
+/// 按序号存储的事件中仍保留的序号范围，裁剪时从 start 开始删除
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventRange {
+    pub start: u64, // 最早保留的事件序号
+    pub end: u64,   // 下一个事件的序号
+}
+
+impl EventRange {
+    /// 分配下一个事件的序号
+    pub fn push(&mut self) -> u64 {
+        let sequence = self.end;
+        self.end += 1;
+        sequence
+    }
+
+    /// 最近 limit 个仍保留的事件序号，按时间先后排列
+    pub fn latest(&self, limit: usize) -> std::ops::Range<u64> {
+        self.end.saturating_sub(limit as u64).max(self.start)..self.end
+    }
+}
+
+/// 测验状态，根据开始和结束时间计算，已取消的测验始终为 Cancelled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum QuizStatus {
+    /// 尚未开始
+    Upcoming,
+    /// 进行中
+    Live,
+    /// 已结束
+    Ended,
+    /// 已取消并退款
+    Cancelled,
+}
+
+/// 测验列表的排序字段，时间字段按数值比较
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum QuizSortField {
+    /// 测验ID
+    Id,
+    /// 标题
+    Title,
+    /// 创建时间
+    CreatedAt,
+    /// 开始时间
+    StartTime,
+    /// 结束时间
+    EndTime,
+    /// 平均星级
+    Rating,
+    /// 参与人数
+    ParticipantCount,
+}
+
+/// 按 sort_by 指定的字段排序测验列表，时间字段按数值比较；未指定时按ID升序。
+/// 字段相同的测验按ID升序排列，保证客户端分页顺序稳定。participant_counts 只在按参与人数排序时使用
+pub fn sort_quiz_sets(
+    quiz_sets: &mut [state::QuizSet],
+    sort_by: Option<QuizSortField>,
+    descending: bool,
+    participant_counts: &HashMap<u64, usize>,
+) {
+    let direction = |ordering: Ordering| {
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    let participant_count = |quiz: &state::QuizSet| {
+        participant_counts
+            .get(&quiz.id)
+            .copied()
+            .unwrap_or_default()
+    };
+    quiz_sets.sort_by_key(|quiz| quiz.id);
+    match sort_by {
+        None => {}
+        Some(QuizSortField::Id) => quiz_sets.sort_by(|a, b| direction(a.id.cmp(&b.id))),
+        Some(QuizSortField::Title) => quiz_sets.sort_by(|a, b| direction(a.title.cmp(&b.title))),
+        Some(QuizSortField::CreatedAt) => {
+            quiz_sets.sort_by(|a, b| direction(a.created_at.micros().cmp(&b.created_at.micros())))
+        }
+        Some(QuizSortField::StartTime) => {
+            quiz_sets.sort_by(|a, b| direction(a.start_time.micros().cmp(&b.start_time.micros())))
+        }
+        Some(QuizSortField::EndTime) => {
+            quiz_sets.sort_by(|a, b| direction(a.end_time.micros().cmp(&b.end_time.micros())))
+        }
+        Some(QuizSortField::Rating) => {
+            quiz_sets.sort_by(|a, b| direction(a.average_rating().total_cmp(&b.average_rating())))
+        }
+        Some(QuizSortField::ParticipantCount) => {
+            quiz_sets.sort_by(|a, b| direction(participant_count(a).cmp(&participant_count(b))))
+        }
+    }
+}
+
 /// Quiz集合视图
 #[derive(Debug, Serialize, Deserialize, SimpleObject)]
 pub struct QuizSetView {
@@ -110,6 +812,125 @@ pub struct QuizSetView {
     pub start_time: String, // 微秒时间戳字符串
     pub end_time: String,   // 微秒时间戳字符串
     pub created_at: String, // 微秒时间戳字符串
+    pub start_time_micros: u64,
+    pub end_time_micros: u64,
+    pub created_at_micros: u64,
+    pub tags: Vec<String>,
+    pub status: QuizStatus,
+    pub image_url: Option<String>,
+    pub penalty: Option<u32>,
+    pub team_ranking: TeamRanking,
+    pub delivery_mode: DeliveryMode,
+    pub revealed_questions: u32, // 实时模式下已发放的题目数量
+    pub question_open: bool,     // 实时模式下最后发放的题目是否仍在作答中
+    pub grace_period_secs: u64,
+    pub allow_creator_participation: bool,
+    pub total_points: u32,
+    pub average_rating: f64, // 平均星级，没有评价时为0
+    pub rating_count: u32,
+    pub hidden: bool,
+    pub report_count: u32, // 未处理的举报数量
+    pub prize: Amount,
+    pub prize_split: Vec<u8>,
+    pub prize_distributed: bool,
+    pub entry_fee: Amount,
+    pub pool_total: Amount, // 托管奖金加已收取的报名费
+    pub cancelled: bool,    // 已取消并退款
+}
+
+impl QuizSetView {
+    /// 由存储的测验构建视图，status 根据当前时间计算，实时模式只包含已发放的题目
+    pub fn new(quiz: &state::QuizSet, now: Timestamp) -> Self {
+        QuizSetView {
+            id: quiz.id,
+            title: quiz.title.clone(),
+            description: quiz.description.clone(),
+            creator: quiz.creator.clone(),
+            questions: quiz
+                .questions
+                .iter()
+                .take(quiz.visible_question_count())
+                .map(QuestionView::from)
+                .collect(),
+            start_time: quiz.start_time.micros().to_string(),
+            end_time: quiz.end_time.micros().to_string(),
+            created_at: quiz.created_at.micros().to_string(),
+            start_time_micros: quiz.start_time.micros(),
+            end_time_micros: quiz.end_time.micros(),
+            created_at_micros: quiz.created_at.micros(),
+            tags: quiz.tags.clone(),
+            status: quiz.status(now),
+            image_url: quiz.image_url.clone(),
+            penalty: quiz.penalty,
+            team_ranking: quiz.team_ranking,
+            delivery_mode: quiz.delivery_mode,
+            revealed_questions: quiz.revealed_questions,
+            question_open: quiz.question_open,
+            grace_period_secs: quiz.grace_period_secs,
+            allow_creator_participation: quiz.allow_creator_participation,
+            total_points: quiz.total_points,
+            average_rating: quiz.average_rating(),
+            rating_count: quiz.rating_count,
+            hidden: quiz.hidden,
+            report_count: quiz.report_count,
+            prize: quiz.prize,
+            prize_split: quiz.prize_split.clone(),
+            prize_distributed: quiz.prize_distributed,
+            entry_fee: quiz.entry_fee,
+            pool_total: quiz.pool_total,
+            cancelled: quiz.cancelled,
+        }
+    }
+}
+
+/// 测验卡片视图，不包含问题内容，用于列表展示
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct QuizCardView {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub creator: String,
+    pub start_time: String, // 微秒时间戳字符串
+    pub end_time: String,   // 微秒时间戳字符串
+    pub created_at: String, // 微秒时间戳字符串
+    pub start_time_micros: u64,
+    pub end_time_micros: u64,
+    pub created_at_micros: u64,
+    pub tags: Vec<String>,
+    pub status: QuizStatus,
+    pub question_count: u32,
+    pub participant_count: u32,
+    pub image_url: Option<String>,
+}
+
+impl QuizCardView {
+    pub fn new(quiz: &state::QuizSet, participant_count: u32, now: Timestamp) -> Self {
+        QuizCardView {
+            id: quiz.id,
+            title: quiz.title.clone(),
+            description: quiz.description.clone(),
+            creator: quiz.creator.clone(),
+            start_time: quiz.start_time.micros().to_string(),
+            end_time: quiz.end_time.micros().to_string(),
+            created_at: quiz.created_at.micros().to_string(),
+            start_time_micros: quiz.start_time.micros(),
+            end_time_micros: quiz.end_time.micros(),
+            created_at_micros: quiz.created_at.micros(),
+            tags: quiz.tags.clone(),
+            status: quiz.status(now),
+            question_count: quiz.questions.len() as u32,
+            participant_count,
+            image_url: quiz.image_url.clone(),
+        }
+    }
+}
+
+/// 基于游标分页的测验列表
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct QuizSetPage {
+    pub items: Vec<QuizSetView>,
+    pub has_more: bool,
+    pub next_cursor: Option<u64>, // 作为下一页的 after_id 传入
 }
 
 /// 问题视图
@@ -119,24 +940,447 @@ pub struct QuestionView {
     pub text: String,
     pub options: Vec<String>,
     pub points: u32,
+    pub option_ids: Vec<u32>, // 展示顺序中每个选项对应的原始选项索引
+    pub image_url: Option<String>,
+    pub option_images: Option<Vec<Option<String>>>, // 与 options 的展示顺序一致
+    pub media_url: Option<String>,
+    pub single_choice: bool,
 }
 
-/// 查询响应
-#[derive(Debug, Serialize, Deserialize)]
-pub enum QueryResponse {
-    /// 所有Quiz集合
-    QuizSets(Vec<QuizSetView>),
-    /// Quiz集合详情
-    QuizSet(Option<QuizSetView>),
-    /// 用户尝试记录列表
-    UserAttempts(Vec<QuizAttempt>),
-    Leaderboard(Vec<UserAttemptView>),
-    QuizLeaderboard(Vec<UserAttemptView>),
-    UserParticipations(Vec<u64>),
-    /// 用户创建的测验集合
-    UserCreatedQuizzes(Vec<QuizSetView>),
-    /// 用户参与的测验集合
-    UserParticipatedQuizzes(Vec<QuizSetView>),
+impl From<&state::Question> for QuestionView {
+    fn from(question: &state::Question) -> Self {
+        QuestionView {
+            id: question.id,
+            text: question.text.clone(),
+            options: question.options.clone(),
+            points: question.points,
+            option_ids: (0..question.options.len() as u32).collect(),
+            image_url: question.image_url.clone(),
+            option_images: question.option_images.clone(),
+            media_url: question.media_url.clone(),
+            single_choice: question.single_choice,
+        }
+    }
+}
+
+/// 根据测验ID和用户生成确定性的乱序种子（FNV-1a）
+pub fn shuffle_seed(quiz_id: u64, user: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in quiz_id.to_le_bytes().iter().chain(user.as_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// 用种子生成确定性排列，返回值的第 i 项是展示位置 i 对应的原始索引
+fn permutation(mut seed: u64, len: usize) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..len as u32).collect();
+    for i in (1..len).rev() {
+        let j = (splitmix64(&mut seed) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+/// 用户看到的题目顺序
+pub fn question_order(quiz_id: u64, user: &str, len: usize) -> Vec<u32> {
+    permutation(shuffle_seed(quiz_id, user), len)
+}
+
+/// 用户看到的某个问题的选项顺序
+pub fn option_order(quiz_id: u64, user: &str, question_id: u32, len: usize) -> Vec<u32> {
+    let seed = shuffle_seed(quiz_id, user)
+        ^ (u64::from(question_id) + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    permutation(seed, len)
+}
+
+/// 将按乱序提交的答案还原为原始题目和选项顺序。
+/// 答案数量与题目数量不一致或选项索引越界时返回错误
+pub fn unshuffle_answers(
+    quiz_set: &state::QuizSet,
+    user: &str,
+    answers: Vec<Vec<u32>>,
+) -> Result<Vec<Vec<u32>>, ScoringError> {
+    if answers.len() != quiz_set.questions.len() {
+        return Err(ScoringError::AnswerCountMismatch {
+            expected: quiz_set.questions.len(),
+            actual: answers.len(),
+        });
+    }
+    let order = question_order(quiz_set.id, user, quiz_set.questions.len());
+    let mut unshuffled = vec![Vec::new(); quiz_set.questions.len()];
+    for (displayed, selected) in answers.into_iter().enumerate() {
+        let question = &quiz_set.questions[order[displayed] as usize];
+        let options = option_order(quiz_set.id, user, question.id, question.options.len());
+        unshuffled[order[displayed] as usize] = selected
+            .into_iter()
+            .map(|option| {
+                options
+                    .get(option as usize)
+                    .copied()
+                    .ok_or(ScoringError::OptionOutOfRange {
+                        question_id: question.id,
+                        option,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(unshuffled)
+}
+
+/// 去除昵称首尾空白，并检查非空、不超过32个字符且不含控制字符。
+/// 合约写入和服务查询都使用此函数，保证以昵称为键的记录能被查到
+pub fn normalize_nick_name(nick_name: &str) -> Result<String, String> {
+    let nick_name = nick_name.trim();
+    if nick_name.is_empty() {
+        return Err("Nickname must not be empty".to_string());
+    }
+    if nick_name.chars().count() > 32 {
+        return Err("Nickname is too long (maximum 32 characters)".to_string());
+    }
+    if nick_name.chars().any(char::is_control) {
+        return Err("Nickname must not contain control characters".to_string());
+    }
+    Ok(nick_name.to_string())
+}
+
+/// 检查图片或媒体URL为 http(s) 地址且不超过2048个字符
+pub fn validate_url(url: &str, name: &str) -> Result<(), String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("{name} must start with http:// or https://"));
+    }
+    if url.len() > 2048 {
+        return Err(format!("{name} is too long (maximum 2048 characters)"));
+    }
+    Ok(())
+}
+
+/// 将毫秒时间戳转换为 Timestamp，优先使用数值字段，兼容旧的字符串字段
+pub fn millis_to_timestamp(
+    millis: Option<u64>,
+    legacy: Option<&str>,
+    name: &str,
+) -> Result<Timestamp, String> {
+    let millis = match (millis, legacy) {
+        (Some(millis), _) => millis,
+        (None, Some(legacy)) => legacy
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid {name} format"))?,
+        (None, None) => return Err(format!("Missing {name}")),
+    };
+    millis
+        .checked_mul(1000) // 毫秒转微秒
+        .map(Timestamp::from)
+        .ok_or_else(|| format!("{name} overflow when converting to microseconds"))
+}
+
+/// 检查每题分值为1到1000，并返回不超过 u32 范围的总分
+pub fn total_question_points(questions: &[QuestionParams]) -> Result<u32, String> {
+    if !questions.iter().all(|q| (1..=1000).contains(&q.points)) {
+        return Err("Question points must be between 1 and 1000".to_string());
+    }
+    questions
+        .iter()
+        .try_fold(0u32, |total, q| total.checked_add(q.points))
+        .ok_or_else(|| "Total quiz points overflow".to_string())
+}
+
+/// 检查正确选项非空、不越界且不重复，单选题只能有一个正确选项
+pub fn validate_correct_options(question: &QuestionParams) -> Result<(), String> {
+    if question.correct_options.is_empty() {
+        return Err("Questions must have at least one correct option".to_string());
+    }
+    for (i, &option) in question.correct_options.iter().enumerate() {
+        if option as usize >= question.options.len() {
+            return Err(format!("Correct option {option} is out of range"));
+        }
+        if question.correct_options[..i].contains(&option) {
+            return Err(format!("Correct option {option} is listed more than once"));
+        }
+    }
+    if question.single_choice == Some(true) && question.correct_options.len() != 1 {
+        return Err("Single choice questions must have exactly one correct option".to_string());
+    }
+    Ok(())
+}
+
+/// 参与者昵称分页：默认保持参与顺序，sort_by_name 为 true 时按昵称排序，跳过 offset 个后最多返回 limit 个
+pub fn page_participants(
+    mut participants: Vec<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    sort_by_name: bool,
+) -> Vec<String> {
+    if sort_by_name {
+        participants.sort();
+    }
+    participants
+        .into_iter()
+        .skip(offset.unwrap_or(0) as usize)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect()
+}
+
+/// 计分失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScoringError {
+    /// 答案数量与题目数量不一致
+    AnswerCountMismatch { expected: usize, actual: usize },
+    /// 选项索引超出题目的选项范围
+    OptionOutOfRange { question_id: u32, option: u32 },
+    /// 同一题目中重复选择了同一选项
+    DuplicateSelection { question_id: u32, option: u32 },
+    /// 单选题选择了多个选项
+    MultipleSelections { question_id: u32 },
+}
+
+impl std::fmt::Display for ScoringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoringError::AnswerCountMismatch { expected, actual } => write!(
+                f,
+                "Answer count mismatch with questions: expected {expected}, got {actual}"
+            ),
+            ScoringError::OptionOutOfRange {
+                question_id,
+                option,
+            } => write!(
+                f,
+                "Answer option {option} is out of range for question {question_id}"
+            ),
+            ScoringError::DuplicateSelection {
+                question_id,
+                option,
+            } => write!(
+                f,
+                "Answer option {option} is selected more than once for question {question_id}"
+            ),
+            ScoringError::MultipleSelections { question_id } => write!(
+                f,
+                "Question {question_id} is single choice but multiple options were selected"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScoringError {}
+
+/// 计分结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredAttempt {
+    /// 扣分后的总分，最低为0
+    pub score: u32,
+    /// 每题得分，不含扣分
+    pub question_points: Vec<u32>,
+    /// 按原始题目顺序排列的答案，与 UserAttempt.answers 的格式一致
+    pub answers: Vec<Vec<u32>>,
+}
+
+/// 检查单个题目的选择：选项不能越界或重复，单选题最多选择一个选项
+pub fn validate_selection(
+    question: &state::Question,
+    selected: &[u32],
+) -> Result<(), ScoringError> {
+    if question.single_choice && selected.len() > 1 {
+        return Err(ScoringError::MultipleSelections {
+            question_id: question.id,
+        });
+    }
+    for (i, &option) in selected.iter().enumerate() {
+        if option as usize >= question.options.len() {
+            return Err(ScoringError::OptionOutOfRange {
+                question_id: question.id,
+                option,
+            });
+        }
+        if selected[..i].contains(&option) {
+            return Err(ScoringError::DuplicateSelection {
+                question_id: question.id,
+                option,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 计算答案得分。答对得该题分值，答错（非空）按 penalty 扣分，扣分从总分中减去且总分最低为0。
+/// answers 需按原始题目顺序排列，每题的选项不能越界或重复
+pub fn score_answers(
+    questions: &[state::Question],
+    penalty: Option<u32>,
+    answers: &[Vec<u32>],
+) -> Result<ScoredAttempt, ScoringError> {
+    if answers.len() != questions.len() {
+        return Err(ScoringError::AnswerCountMismatch {
+            expected: questions.len(),
+            actual: answers.len(),
+        });
+    }
+
+    let mut points = 0u32;
+    let mut penalties = 0u32;
+    let mut question_points = Vec::with_capacity(answers.len());
+    for (question, selected) in questions.iter().zip(answers) {
+        validate_selection(question, selected)?;
+        if question.is_correct(selected) {
+            points = points.saturating_add(question.points);
+            question_points.push(question.points);
+        } else {
+            if !selected.is_empty() {
+                penalties = penalties.saturating_add(penalty.unwrap_or(0));
+            }
+            question_points.push(0);
+        }
+    }
+
+    Ok(ScoredAttempt {
+        score: points.saturating_sub(penalties),
+        question_points,
+        answers: answers.to_vec(),
+    })
+}
+
+/// 测验及当前用户的答题记录
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct QuizWithAttempt {
+    pub quiz: Option<QuizSetView>,
+    pub attempt: Option<UserAttemptView>,
+}
+
+/// 答题回顾中单个题目的结果，测验结束前不公开正确答案和判分
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct QuestionResult {
+    pub question_id: u32,
+    pub selected: Vec<u32>,
+    pub correct_options: Option<Vec<u32>>,
+    pub is_correct: Option<bool>,
+    pub points_awarded: Option<u32>,
+}
+
+/// 得分预览
+#[derive(Debug, Serialize, Deserialize, SimpleObject)]
+pub struct ScorePreview {
+    pub score: u32,
+    pub question_points: Vec<u32>, // 每题得分，不含扣分
+}
+
+/// 将按乱序题目顺序提交的每题用时还原为原始题目顺序
+pub fn unshuffle_times(quiz_set: &state::QuizSet, user: &str, times: Vec<u64>) -> Vec<u64> {
+    let order = question_order(quiz_set.id, user, quiz_set.questions.len());
+    let mut unshuffled = vec![0; quiz_set.questions.len()];
+    for (displayed, time) in times.into_iter().enumerate() {
+        unshuffled[order[displayed] as usize] = time;
+    }
+    unshuffled
+}
+
+/// 计算前 winners 名各自的奖金和需要退还创建者的金额。
+/// 每个名次的奖金向下取整，余数归第一名；没有获奖者的名次奖金退还创建者
+pub fn prize_shares(prize: Amount, split: &[u8], winners: usize) -> (Vec<Amount>, Amount) {
+    let total = u128::from(prize);
+    let mut shares: Vec<u128> = split
+        .iter()
+        .map(|&percent| {
+            let percent = u128::from(percent);
+            total / 100 * percent + total % 100 * percent / 100
+        })
+        .collect();
+    let distributed: u128 = shares.iter().sum();
+    if let Some(first) = shares.first_mut() {
+        *first += total - distributed;
+    }
+    let refund: u128 = shares.iter().skip(winners).sum();
+    shares.truncate(winners);
+    (
+        shares.into_iter().map(Amount::from_attos).collect(),
+        Amount::from_attos(refund),
+    )
+}
+
+/// 按排行榜顺序计算获奖者的账户和奖金，以及退还创建者的金额。
+/// 没有获奖者的名次和没有签名账户的获奖者的奖金都退还创建者
+pub fn prize_payouts(
+    prize: Amount,
+    split: &[u8],
+    winner_owners: &[Option<AccountOwner>],
+) -> (Vec<(AccountOwner, Amount)>, Amount) {
+    let (shares, mut refund) = prize_shares(prize, split, winner_owners.len());
+    let mut payouts = Vec::new();
+    for (owner, share) in winner_owners.iter().zip(shares) {
+        match owner {
+            Some(owner) => payouts.push((*owner, share)),
+            None => refund = refund.saturating_add(share),
+        }
+    }
+    (payouts, refund)
+}
+
+/// 应用配置，实例化时以JSON传入，未提供的字段使用默认值，至少需要一个管理员
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "AppConfigInput")]
+#[serde(default)]
+pub struct AppConfig {
+    /// 每个账户24小时内最多可创建的测验数量
+    pub max_quizzes_per_user_per_day: u32,
+    /// 每个测验最多的问题数量
+    pub max_questions_per_quiz: u32,
+    /// 每个问题最多的选项数量
+    pub max_options_per_question: u32,
+    /// 测验结束多少天后可以归档
+    pub archive_after_days: u32,
+    /// 管理员账户
+    pub admins: Vec<AccountOwner>,
+    /// 每个测验缓存的排行榜条目数量
+    pub leaderboard_size: u32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            max_quizzes_per_user_per_day: 20,
+            max_questions_per_quiz: 200,
+            max_options_per_question: 12,
+            archive_after_days: 30,
+            admins: Vec::new(),
+            leaderboard_size: 100,
+        }
+    }
+}
+
+impl AppConfig {
+    /// 判断账户是否为应用管理员
+    pub fn is_admin(&self, owner: &AccountOwner) -> bool {
+        self.admins.contains(owner)
+    }
+
+    /// 检查配置：至少需要一个管理员，各项上限和排行榜大小不能为0
+    pub fn validate(&self) -> Result<(), String> {
+        if self.admins.is_empty() {
+            return Err("At least one admin is required".to_string());
+        }
+        if self.max_quizzes_per_user_per_day == 0 {
+            return Err("Max quizzes per user per day must be greater than 0".to_string());
+        }
+        if self.max_questions_per_quiz == 0 {
+            return Err("Max questions per quiz must be greater than 0".to_string());
+        }
+        if self.max_options_per_question == 0 {
+            return Err("Max options per question must be greater than 0".to_string());
+        }
+        if self.leaderboard_size == 0 {
+            return Err("Leaderboard size must be greater than 0".to_string());
+        }
+        Ok(())
+    }
 }
 
 impl ContractAbi for QuizAbi {
@@ -147,4 +1391,849 @@ impl ContractAbi for QuizAbi {
 impl ServiceAbi for QuizAbi {
     type Query = async_graphql::Request;
     type QueryResponse = async_graphql::Response;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state::{Question, QuizSet, UserAttempt};
+
+    fn question(id: u32, option_count: usize, correct_options: &[u32], points: u32) -> Question {
+        Question {
+            id,
+            text: format!("Question {id}"),
+            options: (0..option_count).map(|i| format!("Option {i}")).collect(),
+            correct_options: correct_options.to_vec(),
+            points,
+            image_url: None,
+            option_images: None,
+            media_url: None,
+            single_choice: correct_options.len() == 1,
+        }
+    }
+
+    fn owner(byte: u8) -> AccountOwner {
+        AccountOwner::Address20([byte; 20])
+    }
+
+    fn sample_quiz(questions: Vec<Question>) -> QuizSet {
+        let total_points = questions.iter().map(|q| q.points).sum();
+        QuizSet {
+            id: 7,
+            title: "Sample".to_string(),
+            description: String::new(),
+            creator: "creator".to_string(),
+            creator_owner: owner(1),
+            questions,
+            time_limit: 600,
+            start_time: Timestamp::from(1_000),
+            end_time: Timestamp::from(2_000),
+            created_at: Timestamp::from(500),
+            tags: Vec::new(),
+            max_participants: None,
+            allow_cloning: false,
+            image_url: None,
+            penalty: None,
+            team_ranking: TeamRanking::TotalScore,
+            delivery_mode: DeliveryMode::AllAtOnce,
+            revealed_questions: 0,
+            question_open: false,
+            question_revealed_at: Timestamp::from(0),
+            grace_period_secs: 0,
+            allow_creator_participation: false,
+            total_points,
+            rating_count: 0,
+            rating_total: 0,
+            hidden: false,
+            report_count: 0,
+            prize: Amount::ZERO,
+            prize_split: Vec::new(),
+            prize_distributed: false,
+            entry_fee: Amount::ZERO,
+            pool_total: Amount::ZERO,
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn quiz_ends_after_end_time() {
+        let quiz = sample_quiz(Vec::new());
+        assert!(!quiz.is_ended(Timestamp::from(1_999)));
+        assert!(!quiz.is_ended(Timestamp::from(2_000)));
+        assert!(quiz.is_ended(Timestamp::from(2_001)));
+    }
+
+    #[test]
+    fn cancelled_status_takes_precedence() {
+        let mut quiz = sample_quiz(Vec::new());
+        assert_eq!(quiz.status(Timestamp::from(999)), QuizStatus::Upcoming);
+        assert_eq!(quiz.status(Timestamp::from(1_000)), QuizStatus::Live);
+        assert_eq!(quiz.status(Timestamp::from(2_001)), QuizStatus::Ended);
+
+        quiz.cancelled = true;
+        for now in [999, 1_000, 2_001] {
+            assert_eq!(quiz.status(Timestamp::from(now)), QuizStatus::Cancelled);
+        }
+    }
+
+    #[test]
+    fn quiz_closes_after_grace_period() {
+        let mut quiz = sample_quiz(Vec::new());
+        assert!(!quiz.is_closed(Timestamp::from(2_000)));
+        assert!(quiz.is_closed(Timestamp::from(2_001)));
+
+        quiz.grace_period_secs = 1;
+        assert!(quiz.is_ended(Timestamp::from(2_001)));
+        assert!(!quiz.is_closed(Timestamp::from(1_002_000)));
+        assert!(quiz.is_closed(Timestamp::from(1_002_001)));
+    }
+
+    #[test]
+    fn participant_cap_rejects_extra_entries() {
+        let mut quiz = sample_quiz(Vec::new());
+        assert!(!quiz.is_full(1_000));
+
+        quiz.max_participants = Some(2);
+        assert!(!quiz.is_full(0));
+        assert!(!quiz.is_full(1));
+        // 两个名额已满，第三位参与者被拒绝
+        assert!(quiz.is_full(2));
+    }
+
+    #[test]
+    fn quiz_sets_sort_numerically() {
+        let mut early = sample_quiz(Vec::new());
+        early.id = 10;
+        early.created_at = Timestamp::from(999);
+        let mut late = sample_quiz(Vec::new());
+        late.id = 9;
+        late.created_at = Timestamp::from(1_000_000_000);
+        let ids = |quiz_sets: &[QuizSet]| quiz_sets.iter().map(|q| q.id).collect::<Vec<_>>();
+        let counts = HashMap::new();
+
+        // 按字符串比较时 "10" 会排在 "9" 之前
+        let mut quiz_sets = vec![early.clone(), late.clone()];
+        sort_quiz_sets(&mut quiz_sets, None, false, &counts);
+        assert_eq!(ids(&quiz_sets), [9, 10]);
+        sort_quiz_sets(&mut quiz_sets, Some(QuizSortField::Id), true, &counts);
+        assert_eq!(ids(&quiz_sets), [10, 9]);
+
+        // 按字符串比较时 "1000000000" 会排在 "999" 之前
+        sort_quiz_sets(
+            &mut quiz_sets,
+            Some(QuizSortField::CreatedAt),
+            false,
+            &counts,
+        );
+        assert_eq!(ids(&quiz_sets), [10, 9]);
+        sort_quiz_sets(
+            &mut quiz_sets,
+            Some(QuizSortField::CreatedAt),
+            true,
+            &counts,
+        );
+        assert_eq!(ids(&quiz_sets), [9, 10]);
+    }
+
+    #[test]
+    fn quiz_export_round_trips_through_json() {
+        let mut quiz = sample_quiz(scoring_questions());
+        quiz.tags = vec!["rust".to_string()];
+        quiz.max_participants = Some(3);
+        quiz.penalty = Some(2);
+        quiz.grace_period_secs = 60;
+        let json = serde_json::to_string(&QuizExport::from(&quiz)).unwrap();
+
+        let export = QuizExport::from_json(&json).unwrap();
+        assert_eq!(export.title, quiz.title);
+        assert_eq!(export.tags, quiz.tags);
+        assert_eq!(export.max_participants, Some(3));
+        assert_eq!(export.penalty, Some(2));
+        assert_eq!(export.grace_period_secs, 60);
+        assert_eq!(export.questions.len(), 2);
+        assert_eq!(export.questions[1].correct_options, [0, 2]);
+        assert_eq!(export.questions[1].points, 20);
+        assert_eq!(serde_json::to_string(&export).unwrap(), json);
+    }
+
+    #[test]
+    fn quiz_export_rejects_malformed_payloads() {
+        let quiz = sample_quiz(scoring_questions());
+        let json = serde_json::to_string(&QuizExport::from(&quiz)).unwrap();
+
+        let malformed = json.replace("\"time_limit\":600", "\"time_limit\":\"600\"");
+        assert_ne!(malformed, json);
+        let error = QuizExport::from_json(&malformed).unwrap_err();
+        assert!(
+            error.starts_with("Invalid quiz payload: invalid type"),
+            "{error}"
+        );
+
+        let missing = json.replace("\"title\":\"Sample\",", "");
+        let error = QuizExport::from_json(&missing).unwrap_err();
+        assert!(error.contains("missing field `title`"), "{error}");
+
+        let future = json.replace("\"version\":1", "\"version\":2");
+        assert_eq!(
+            QuizExport::from_json(&future).unwrap_err(),
+            "Unsupported quiz payload version"
+        );
+
+        let oversized = " ".repeat(MAX_QUIZ_EXPORT_SIZE + 1);
+        assert!(QuizExport::from_json(&oversized)
+            .unwrap_err()
+            .contains("too large"));
+    }
+
+    #[test]
+    fn nick_names_are_trimmed_and_checked() {
+        assert_eq!(normalize_nick_name("  alice \t").unwrap(), "alice");
+        assert_eq!(
+            normalize_nick_name(&"测".repeat(32)).unwrap(),
+            "测".repeat(32)
+        );
+        assert_eq!(
+            normalize_nick_name("   ").unwrap_err(),
+            "Nickname must not be empty"
+        );
+        assert_eq!(
+            normalize_nick_name(&"a".repeat(33)).unwrap_err(),
+            "Nickname is too long (maximum 32 characters)"
+        );
+        assert_eq!(
+            normalize_nick_name("al\nice").unwrap_err(),
+            "Nickname must not contain control characters"
+        );
+    }
+
+    #[test]
+    fn urls_must_be_short_http_addresses() {
+        assert!(validate_url("https://example.com/a.png", "Image URL").is_ok());
+        assert!(validate_url("http://example.com", "Image URL").is_ok());
+        assert_eq!(
+            validate_url("ftp://example.com", "Image URL").unwrap_err(),
+            "Image URL must start with http:// or https://"
+        );
+        assert!(validate_url("javascript:alert(1)", "Image URL").is_err());
+
+        let url = format!("https://{}", "a".repeat(2040));
+        assert!(validate_url(&url, "Image URL").is_ok());
+        assert_eq!(
+            validate_url(&format!("{url}a"), "Image URL").unwrap_err(),
+            "Image URL is too long (maximum 2048 characters)"
+        );
+    }
+
+    #[test]
+    fn millis_convert_to_timestamps() {
+        assert_eq!(
+            millis_to_timestamp(Some(1_500), None, "start time"),
+            Ok(Timestamp::from(1_500_000))
+        );
+        // 数值字段优先于旧的字符串字段
+        assert_eq!(
+            millis_to_timestamp(Some(1), Some("2"), "start time"),
+            Ok(Timestamp::from(1_000))
+        );
+        assert_eq!(
+            millis_to_timestamp(None, Some("2"), "start time"),
+            Ok(Timestamp::from(2_000))
+        );
+        assert_eq!(
+            millis_to_timestamp(None, Some("soon"), "start time"),
+            Err("Invalid start time format".to_string())
+        );
+        assert_eq!(
+            millis_to_timestamp(None, None, "end time"),
+            Err("Missing end time".to_string())
+        );
+        assert_eq!(
+            millis_to_timestamp(Some(u64::MAX), None, "end time"),
+            Err("end time overflow when converting to microseconds".to_string())
+        );
+    }
+
+    #[test]
+    fn event_range_tracks_retained_events() {
+        let mut range = EventRange::default();
+        assert_eq!(range.latest(20), 0..0);
+        for expected in 0..5 {
+            assert_eq!(range.push(), expected);
+        }
+        assert_eq!(range.latest(3), 2..5);
+        assert_eq!(range.latest(20), 0..5);
+
+        // 裁剪后不再返回已删除的事件
+        range.start = 4;
+        assert_eq!(range.latest(3), 4..5);
+        assert_eq!(range.push(), 5);
+        assert_eq!(range.latest(20), 4..6);
+    }
+
+    fn question_params(
+        option_count: usize,
+        correct_options: &[u32],
+        points: u32,
+    ) -> QuestionParams {
+        QuestionParams {
+            text: "Question".to_string(),
+            options: (0..option_count).map(|i| format!("Option {i}")).collect(),
+            correct_options: correct_options.to_vec(),
+            points,
+            image_url: None,
+            option_images: None,
+            media_url: None,
+            single_choice: Some(correct_options.len() == 1),
+        }
+    }
+
+    #[test]
+    fn question_points_are_bounded() {
+        let points = |values: &[u32]| {
+            let questions: Vec<_> = values
+                .iter()
+                .map(|&points| question_params(2, &[0], points))
+                .collect();
+            total_question_points(&questions)
+        };
+        assert_eq!(points(&[1, 1000, 20]), Ok(1021));
+        assert_eq!(points(&[]), Ok(0));
+        assert_eq!(
+            points(&[10, 0]),
+            Err("Question points must be between 1 and 1000".to_string())
+        );
+        assert_eq!(
+            points(&[1001]),
+            Err("Question points must be between 1 and 1000".to_string())
+        );
+    }
+
+    #[test]
+    fn correct_options_must_be_valid_indices() {
+        assert!(validate_correct_options(&question_params(4, &[3], 10)).is_ok());
+        assert!(validate_correct_options(&question_params(4, &[0, 2], 10)).is_ok());
+        assert_eq!(
+            validate_correct_options(&question_params(4, &[], 10)),
+            Err("Questions must have at least one correct option".to_string())
+        );
+        assert_eq!(
+            validate_correct_options(&question_params(4, &[5], 10)),
+            Err("Correct option 5 is out of range".to_string())
+        );
+        assert_eq!(
+            validate_correct_options(&question_params(4, &[4], 10)),
+            Err("Correct option 4 is out of range".to_string())
+        );
+        assert_eq!(
+            validate_correct_options(&question_params(4, &[1, 1], 10)),
+            Err("Correct option 1 is listed more than once".to_string())
+        );
+
+        let mut single = question_params(4, &[0, 1], 10);
+        single.single_choice = Some(true);
+        assert_eq!(
+            validate_correct_options(&single),
+            Err("Single choice questions must have exactly one correct option".to_string())
+        );
+    }
+
+    #[test]
+    fn participants_are_paged() {
+        let participants: Vec<String> = ["carol", "alice", "bob"].map(String::from).to_vec();
+        let page = |limit, offset, sort_by_name| {
+            page_participants(participants.clone(), limit, offset, sort_by_name)
+        };
+
+        assert_eq!(page(None, None, false), ["carol", "alice", "bob"]);
+        assert_eq!(page(None, None, true), ["alice", "bob", "carol"]);
+        assert_eq!(page(Some(2), None, false), ["carol", "alice"]);
+        assert_eq!(page(Some(2), Some(2), false), ["bob"]);
+        assert_eq!(page(Some(1), Some(1), true), ["bob"]);
+        assert!(page(None, Some(3), false).is_empty());
+        assert!(page(Some(0), None, false).is_empty());
+    }
+
+    fn shuffled_quiz() -> QuizSet {
+        sample_quiz(vec![
+            question(0, 4, &[1], 10),
+            question(1, 3, &[0, 2], 20),
+            question(2, 5, &[4], 30),
+            question(3, 2, &[0], 40),
+            question(4, 6, &[2, 3, 5], 50),
+        ])
+    }
+
+    /// 按用户看到的乱序排列答案，与前端提交的格式一致
+    fn shuffle_answers(quiz: &QuizSet, user: &str, answers: &[Vec<u32>]) -> Vec<Vec<u32>> {
+        question_order(quiz.id, user, quiz.questions.len())
+            .into_iter()
+            .map(|original| {
+                let question = &quiz.questions[original as usize];
+                let options = option_order(quiz.id, user, question.id, question.options.len());
+                answers[original as usize]
+                    .iter()
+                    .map(|option| options.iter().position(|o| o == option).unwrap() as u32)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn is_permutation(order: &[u32], len: usize) -> bool {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        sorted == (0..len as u32).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn question_order_is_reproducible_permutation() {
+        let order = question_order(7, "alice", 10);
+        assert_eq!(order, question_order(7, "alice", 10));
+        assert!(is_permutation(&order, 10));
+        assert_ne!(order, question_order(7, "bob", 10));
+        assert_ne!(order, question_order(8, "alice", 10));
+        assert!(question_order(7, "alice", 0).is_empty());
+    }
+
+    #[test]
+    fn option_order_is_reproducible_permutation() {
+        let order = option_order(7, "alice", 3, 8);
+        assert_eq!(order, option_order(7, "alice", 3, 8));
+        assert!(is_permutation(&order, 8));
+        assert_ne!(order, option_order(7, "alice", 4, 8));
+        assert_ne!(order, option_order(7, "bob", 3, 8));
+    }
+
+    #[test]
+    fn unshuffle_answers_restores_original_order() {
+        let quiz = shuffled_quiz();
+        let answers: Vec<Vec<u32>> = quiz
+            .questions
+            .iter()
+            .map(|q| q.correct_options.clone())
+            .collect();
+        for user in ["alice", "bob", "carol"] {
+            let shuffled = shuffle_answers(&quiz, user, &answers);
+            assert_eq!(
+                unshuffle_answers(&quiz, user, shuffled),
+                Ok(answers.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn unshuffle_answers_rejects_invalid_input() {
+        let quiz = shuffled_quiz();
+        let order = question_order(quiz.id, "alice", quiz.questions.len());
+        let mut answers = vec![Vec::new(); quiz.questions.len()];
+        // 展示位置0的题目选择一个不存在的选项
+        let question = &quiz.questions[order[0] as usize];
+        answers[0] = vec![question.options.len() as u32];
+        assert_eq!(
+            unshuffle_answers(&quiz, "alice", answers),
+            Err(ScoringError::OptionOutOfRange {
+                question_id: question.id,
+                option: question.options.len() as u32,
+            })
+        );
+        assert_eq!(
+            unshuffle_answers(&quiz, "alice", vec![Vec::new(); 2]),
+            Err(ScoringError::AnswerCountMismatch {
+                expected: 5,
+                actual: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn unshuffle_times_restores_original_order() {
+        let quiz = shuffled_quiz();
+        let times = vec![100, 200, 300, 400, 500];
+        let order = question_order(quiz.id, "alice", times.len());
+        let shuffled: Vec<u64> = order.iter().map(|&i| times[i as usize]).collect();
+        assert_eq!(unshuffle_times(&quiz, "alice", shuffled), times);
+    }
+
+    fn attempt(user: &str, score: u32, time_taken: u64) -> UserAttempt {
+        UserAttempt {
+            quiz_id: 7,
+            user: user.to_string(),
+            answers: Vec::new(),
+            score,
+            time_taken,
+            completed_at: Timestamp::from(0),
+            per_question_times: Vec::new(),
+            total_points: 100,
+        }
+    }
+
+    #[test]
+    fn cached_leaderboard_matches_scanned_ranking() {
+        let mut attempts = vec![
+            attempt("dave", 80, 500),
+            attempt("alice", 90, 700),
+            attempt("frank", 80, 400),
+            attempt("bob", 90, 700),
+            attempt("erin", 100, 900),
+            attempt("carol", 80, 400),
+        ];
+        let ranked: Vec<String> = rank_attempts(attempts.clone())
+            .into_iter()
+            .map(|attempt| attempt.user)
+            .collect();
+        assert_eq!(ranked, ["erin", "alice", "bob", "carol", "frank", "dave"]);
+
+        // 缓存只保留前N名，无论提交顺序如何都应与扫描结果的前N名相同
+        for _ in 0..2 {
+            for size in 1..=attempts.len() + 1 {
+                let mut cached = Vec::new();
+                for attempt in &attempts {
+                    update_leaderboard_entries(&mut cached, LeaderboardEntry::from(attempt), size);
+                }
+                let scanned: Vec<LeaderboardEntry> = rank_attempts(attempts.clone())
+                    .iter()
+                    .take(size)
+                    .map(LeaderboardEntry::from)
+                    .collect();
+                assert_eq!(cached, scanned);
+            }
+            attempts.reverse();
+        }
+    }
+
+    #[test]
+    fn short_cached_leaderboard_falls_back_to_scan() {
+        // 缓存足够或已包含所有参与者时直接使用缓存
+        assert!(cached_leaderboard_covers(10, 50, 10));
+        assert!(cached_leaderboard_covers(10, 50, 5));
+        assert!(cached_leaderboard_covers(3, 3, 10));
+        // 排行榜大小调大后，旧缓存少于 limit 条而参与者更多
+        assert!(!cached_leaderboard_covers(10, 50, 20));
+        assert!(!cached_leaderboard_covers(0, 1, 3));
+    }
+
+    #[test]
+    fn update_leaderboard_entries_replaces_existing_user() {
+        let mut entries = Vec::new();
+        update_leaderboard_entries(
+            &mut entries,
+            LeaderboardEntry::from(&attempt("alice", 50, 100)),
+            10,
+        );
+        update_leaderboard_entries(
+            &mut entries,
+            LeaderboardEntry::from(&attempt("bob", 60, 100)),
+            10,
+        );
+        update_leaderboard_entries(
+            &mut entries,
+            LeaderboardEntry::from(&attempt("alice", 70, 100)),
+            10,
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].user, "alice");
+        assert_eq!(entries[0].score, 70);
+        assert_eq!(entries[0].score_percent, 70.0);
+    }
+
+    fn admin_config() -> AppConfig {
+        AppConfig {
+            admins: vec![owner(9)],
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn only_configured_admins_are_admins() {
+        let config = admin_config();
+        assert!(config.is_admin(&owner(9)));
+        // 测验创建者不因此成为管理员
+        assert!(!config.is_admin(&owner(1)));
+        assert!(!AppConfig::default().is_admin(&owner(9)));
+    }
+
+    #[test]
+    fn config_requires_admins_and_nonzero_limits() {
+        assert_eq!(admin_config().validate(), Ok(()));
+        assert_eq!(
+            AppConfig::default().validate(),
+            Err("At least one admin is required".to_string())
+        );
+        let zeroed = [
+            AppConfig {
+                max_quizzes_per_user_per_day: 0,
+                ..admin_config()
+            },
+            AppConfig {
+                max_questions_per_quiz: 0,
+                ..admin_config()
+            },
+            AppConfig {
+                max_options_per_question: 0,
+                ..admin_config()
+            },
+            AppConfig {
+                leaderboard_size: 0,
+                ..admin_config()
+            },
+        ];
+        for config in zeroed {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+        // 归档天数为0表示结束后即可归档
+        assert!(AppConfig {
+            archive_after_days: 0,
+            ..admin_config()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn creator_or_admin_can_manage_quiz() {
+        let quiz = sample_quiz(scoring_questions());
+        let config = admin_config();
+        assert!(quiz.can_be_managed_by(&owner(1), &config));
+        assert!(quiz.can_be_managed_by(&owner(9), &config));
+        assert!(!quiz.can_be_managed_by(&owner(2), &config));
+        assert!(!quiz.can_be_managed_by(&owner(9), &AppConfig::default()));
+    }
+
+    #[test]
+    fn creator_participation_requires_opt_in() {
+        let mut quiz = sample_quiz(scoring_questions());
+        assert!(!quiz.allows_participant(Some(&owner(1))));
+        assert!(quiz.allows_participant(Some(&owner(2))));
+        assert!(quiz.allows_participant(None));
+
+        quiz.allow_creator_participation = true;
+        assert!(quiz.allows_participant(Some(&owner(1))));
+    }
+
+    #[test]
+    fn refund_permissions() {
+        let mut quiz = sample_quiz(scoring_questions());
+        let config = admin_config();
+        let before_start = Timestamp::from(999);
+        let after_start = Timestamp::from(1_000);
+
+        assert!(quiz.can_be_refunded_by(&owner(1), &config, before_start));
+        assert!(!quiz.can_be_refunded_by(&owner(1), &config, after_start));
+        assert!(quiz.can_be_refunded_by(&owner(9), &config, before_start));
+        assert!(quiz.can_be_refunded_by(&owner(9), &config, Timestamp::from(5_000)));
+        assert!(!quiz.can_be_refunded_by(&owner(2), &config, before_start));
+
+        // 奖金发放后任何人都不能退款
+        quiz.prize_distributed = true;
+        assert!(!quiz.can_be_refunded_by(&owner(1), &config, before_start));
+        assert!(!quiz.can_be_refunded_by(&owner(9), &config, before_start));
+    }
+
+    #[test]
+    fn entry_fees_fund_the_pool_until_cancelled() {
+        let mut quiz = sample_quiz(scoring_questions());
+        quiz.prize = Amount::from_attos(1_000);
+        quiz.pool_total = quiz.prize;
+        quiz.entry_fee = Amount::from_attos(50);
+        quiz.add_entry_fee();
+        quiz.add_entry_fee();
+        assert_eq!(quiz.pool_total, Amount::from_attos(1_100));
+
+        quiz.cancel();
+        assert!(quiz.cancelled);
+        assert!(quiz.prize_distributed);
+        assert_eq!(quiz.pool_total, Amount::ZERO);
+    }
+
+    #[test]
+    #[should_panic(expected = "Quiz has been cancelled")]
+    fn cancelled_quiz_rejects_entry_fees() {
+        let mut quiz = sample_quiz(scoring_questions());
+        quiz.entry_fee = Amount::from_attos(50);
+        quiz.cancel();
+        quiz.add_entry_fee();
+    }
+
+    fn scoring_questions() -> Vec<Question> {
+        vec![question(0, 4, &[1], 10), question(1, 3, &[0, 2], 20)]
+    }
+
+    #[test]
+    fn score_answers_table() {
+        let questions = scoring_questions();
+        let cases: Vec<(&str, Vec<Vec<u32>>, Result<(u32, Vec<u32>), ScoringError>)> = vec![
+            (
+                "all correct",
+                vec![vec![1], vec![0, 2]],
+                Ok((30, vec![10, 20])),
+            ),
+            (
+                "multi choice in any order",
+                vec![vec![1], vec![2, 0]],
+                Ok((30, vec![10, 20])),
+            ),
+            (
+                "wrong answer is penalized",
+                vec![vec![0], vec![0, 2]],
+                Ok((17, vec![0, 20])),
+            ),
+            (
+                "partial multi choice is wrong",
+                vec![vec![1], vec![0]],
+                Ok((7, vec![10, 0])),
+            ),
+            (
+                "empty answer is not penalized",
+                vec![vec![], vec![0, 2]],
+                Ok((20, vec![0, 20])),
+            ),
+            (
+                "score does not go below zero",
+                vec![vec![0], vec![1]],
+                Ok((0, vec![0, 0])),
+            ),
+            (
+                "too few answers",
+                vec![vec![1]],
+                Err(ScoringError::AnswerCountMismatch {
+                    expected: 2,
+                    actual: 1,
+                }),
+            ),
+            (
+                "too many answers",
+                vec![vec![1], vec![0], vec![0]],
+                Err(ScoringError::AnswerCountMismatch {
+                    expected: 2,
+                    actual: 3,
+                }),
+            ),
+            (
+                "option out of range",
+                vec![vec![4], vec![0]],
+                Err(ScoringError::OptionOutOfRange {
+                    question_id: 0,
+                    option: 4,
+                }),
+            ),
+            (
+                "duplicate selection",
+                vec![vec![1], vec![0, 0]],
+                Err(ScoringError::DuplicateSelection {
+                    question_id: 1,
+                    option: 0,
+                }),
+            ),
+            (
+                "multiple selections on single choice",
+                vec![vec![1, 2], vec![0]],
+                Err(ScoringError::MultipleSelections { question_id: 0 }),
+            ),
+        ];
+
+        for (name, answers, expected) in cases {
+            let result = score_answers(&questions, Some(3), &answers);
+            let actual = result
+                .as_ref()
+                .map(|scored| (scored.score, scored.question_points.clone()))
+                .map_err(Clone::clone);
+            assert_eq!(actual, expected, "{name}");
+            if let Ok(scored) = result {
+                assert_eq!(scored.answers, answers, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn score_answers_without_penalty() {
+        let scored = score_answers(&scoring_questions(), None, &[vec![0], vec![0, 2]]).unwrap();
+        assert_eq!(scored.score, 20);
+        assert_eq!(scored.question_points, vec![0, 20]);
+    }
+
+    fn attos(values: &[u128]) -> Vec<Amount> {
+        values.iter().copied().map(Amount::from_attos).collect()
+    }
+
+    #[test]
+    fn prize_shares_rounding() {
+        let cases: Vec<(&str, u128, &[u8], usize, &[u128], u128)> = vec![
+            ("even split", 1_000, &[50, 30, 20], 3, &[500, 300, 200], 0),
+            (
+                "remainder goes to first place",
+                101,
+                &[33, 33, 34],
+                3,
+                &[34, 33, 34],
+                0,
+            ),
+            ("rounded down per place", 7, &[60, 40], 2, &[5, 2], 0),
+            ("amount smaller than places", 1, &[50, 50], 2, &[1, 0], 0),
+            (
+                "unfilled places are refunded",
+                1_000,
+                &[50, 30, 20],
+                1,
+                &[500],
+                500,
+            ),
+            (
+                "refund includes rounded shares",
+                101,
+                &[33, 33, 34],
+                2,
+                &[34, 33],
+                34,
+            ),
+            ("no participants", 1_000, &[50, 30, 20], 0, &[], 1_000),
+        ];
+        for (name, prize, split, winners, shares, refund) in cases {
+            assert_eq!(
+                prize_shares(Amount::from_attos(prize), split, winners),
+                (attos(shares), Amount::from_attos(refund)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn prize_shares_sum_to_escrowed_amount() {
+        let splits: [&[u8]; 5] = [&[100], &[50, 30, 20], &[33, 33, 34], &[1, 99], &[60, 40]];
+        for prize in [0, 1, 99, 100, 101, 12_345, u128::MAX] {
+            for split in splits {
+                for winners in 0..=split.len() {
+                    let (shares, refund) = prize_shares(Amount::from_attos(prize), split, winners);
+                    assert_eq!(shares.len(), winners);
+                    let paid = shares
+                        .iter()
+                        .fold(refund, |total, &share| total.saturating_add(share));
+                    assert_eq!(paid, Amount::from_attos(prize));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prize_payouts_refund_unclaimed_shares_to_creator() {
+        let prize = Amount::from_attos(1_000);
+        let split = [50, 30, 20];
+
+        let (payouts, refund) = prize_payouts(prize, &split, &[Some(owner(1)), Some(owner(2))]);
+        assert_eq!(
+            payouts,
+            vec![
+                (owner(1), Amount::from_attos(500)),
+                (owner(2), Amount::from_attos(300)),
+            ]
+        );
+        assert_eq!(refund, Amount::from_attos(200));
+
+        // 没有签名账户的获奖者的奖金和空缺名次一起退还
+        let (payouts, refund) = prize_payouts(prize, &split, &[None, Some(owner(2))]);
+        assert_eq!(payouts, vec![(owner(2), Amount::from_attos(300))]);
+        assert_eq!(refund, Amount::from_attos(700));
+
+        let (payouts, refund) = prize_payouts(prize, &split, &[]);
+        assert!(payouts.is_empty());
+        assert_eq!(refund, prize);
+    }
+}
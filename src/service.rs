@@ -2,11 +2,21 @@
 
 use async_graphql::{EmptySubscription, Request, Response, Schema};
 use linera_sdk::graphql::GraphQLMutationRoot;
-use linera_sdk::linera_base_types::WithServiceAbi;
+use linera_sdk::linera_base_types::{AccountOwner, WithServiceAbi};
 use linera_sdk::views::View;
 use linera_sdk::{Service, ServiceRuntime};
-use quiz::state::QuizState;
-use quiz::{Operation, QuestionView, QuizAttempt, QuizSetView, UserAttemptView};
+use quiz::state::{QuizSet, QuizState, UserAttempt};
+use quiz::{
+    ActivityView, AppConfig, ArchivedQuizSummary, BadgeCount, BadgeKind, BadgeView,
+    BankQuestionView, DeliveryMode, DuelStatus, DuelView, GlobalLeaderboardEntry, LeaderboardEntry,
+    Operation, QuestionLeaderboardEntry, QuestionResult, QuestionStatsView, QuestionView,
+    QuizAttempt, QuizCardView, QuizExport, QuizRankView, QuizSetPage, QuizSetView, QuizSortField,
+    QuizStatus, QuizSummaryView, QuizWithAttempt, RatingView, ReportView, ScorePreview,
+    TeamLeaderboardEntry, TeamRanking, UserAttemptView, UserDataExport, UserQuizResult,
+    MAX_ACTIVITY_LIMIT, USER_DATA_EXPORT_PAGE_SIZE, USER_DATA_EXPORT_VERSION,
+};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 linera_sdk::service!(QuizService);
@@ -23,59 +33,153 @@ struct QueryRoot {
 
 #[async_graphql::Object]
 impl QueryRoot {
+    /// 获取测验，被隐藏的测验返回空
     async fn quiz_set(&self, quiz_id: u64) -> Option<QuizSetView> {
         match self.state.quiz_sets.get(&quiz_id).await {
-            Ok(option) => option.map(|quiz| QuizSetView {
-                id: quiz.id,
-                title: quiz.title.clone(),
-                description: quiz.description.clone(),
-                creator: quiz.creator,
-                questions: quiz
-                    .questions
+            Ok(option) => option
+                .filter(|quiz| !quiz.hidden)
+                .map(|quiz| QuizSetView::new(&quiz, self.runtime.system_time())),
+            Err(_) => None,
+        }
+    }
+
+    /// 获取按用户乱序排列题目和选项的测验，用于防止答案共享
+    async fn quiz_set_for_user(&self, quiz_id: u64, user: String) -> Option<QuizSetView> {
+        let user = quiz::normalize_nick_name(&user).ok()?;
+        match self.state.quiz_sets.get(&quiz_id).await {
+            Ok(option) => option.filter(|quiz| !quiz.hidden).map(|quiz| {
+                let mut view = QuizSetView::new(&quiz, self.runtime.system_time());
+                // 实时模式按发放顺序逐题作答，不做乱序
+                if quiz.delivery_mode == DeliveryMode::Live {
+                    return view;
+                }
+                view.questions = quiz::question_order(quiz.id, &user, quiz.questions.len())
                     .iter()
-                    .map(|q| QuestionView {
-                        id: q.id,
-                        text: q.text.clone(),
-                        options: q.options.clone(),
-                        points: q.points,
+                    .map(|&index| {
+                        let q = &quiz.questions[index as usize];
+                        let option_ids = quiz::option_order(quiz.id, &user, q.id, q.options.len());
+                        QuestionView {
+                            id: q.id,
+                            text: q.text.clone(),
+                            options: option_ids
+                                .iter()
+                                .map(|&option| q.options[option as usize].clone())
+                                .collect(),
+                            points: q.points,
+                            image_url: q.image_url.clone(),
+                            media_url: q.media_url.clone(),
+                            single_choice: q.single_choice,
+                            option_images: q.option_images.as_ref().map(|images| {
+                                option_ids
+                                    .iter()
+                                    .map(|&option| images[option as usize].clone())
+                                    .collect()
+                            }),
+                            option_ids,
+                        }
                     })
-                    .collect(),
-                start_time: quiz.start_time.micros().to_string(),
-                end_time: quiz.end_time.micros().to_string(),
-                created_at: quiz.created_at.micros().to_string(),
+                    .collect();
+                view
             }),
             Err(_) => None,
         }
     }
 
-    async fn quiz_sets(&self) -> Vec<QuizSetView> {
+    /// 获取测验列表，可按状态和创建者筛选，并按 sort_by 指定的字段排序，未指定时按ID升序。
+    /// 客户端分页依赖稳定的顺序，排序字段相同的测验按ID排列。
+    /// 该查询会加载全部测验，测验较多时请使用 quiz_sets_page 分页获取
+    async fn quiz_sets(
+        &self,
+        status: Option<QuizStatus>,
+        creator: Option<String>,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizSetView>> {
+        let now = self.runtime.system_time();
+        let quiz_sets = self
+            .filtered_quiz_sets(status, creator, sort_by, descending)
+            .await?;
+        Ok(quiz_sets
+            .iter()
+            .map(|quiz| QuizSetView::new(quiz, now))
+            .collect())
+    }
+
+    /// 获取不含问题内容的测验卡片列表，用于首页展示，筛选和排序参数与 quiz_sets 相同
+    async fn quiz_cards(
+        &self,
+        status: Option<QuizStatus>,
+        creator: Option<String>,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizCardView>> {
+        let now = self.runtime.system_time();
+        let quiz_sets = self
+            .filtered_quiz_sets(status, creator, sort_by, descending)
+            .await?;
+        let mut cards = Vec::with_capacity(quiz_sets.len());
+        for quiz in &quiz_sets {
+            let participant_count = self
+                .state
+                .quiz_participants
+                .get(&quiz.id)
+                .await?
+                .map_or(0, |participants| participants.len() as u32);
+            cards.push(QuizCardView::new(quiz, participant_count, now));
+        }
+        Ok(cards)
+    }
+
+    /// 按测验ID游标分页获取测验列表，只加载当前页的测验
+    async fn quiz_sets_page(
+        &self,
+        after_id: Option<u64>,
+        limit: Option<u32>,
+    ) -> async_graphql::Result<QuizSetPage> {
+        let limit = limit.unwrap_or(20).clamp(1, 100) as usize;
+        let next_quiz_id = *self.state.next_quiz_id.get();
+        let now = self.runtime.system_time();
+        let mut items = Vec::new();
+        let mut has_more = false;
+
+        // 测验ID单调递增，按ID逐个查找即可得到稳定的顺序
+        let mut quiz_id = after_id.map_or(1, |id| id.saturating_add(1));
+        while quiz_id < next_quiz_id {
+            // 被隐藏的测验不出现在列表中
+            let quiz = self.state.quiz_sets.get(&quiz_id).await?;
+            if let Some(quiz) = quiz.filter(|quiz| !quiz.hidden) {
+                if items.len() == limit {
+                    has_more = true;
+                    break;
+                }
+                items.push(QuizSetView::new(&quiz, now));
+            }
+            quiz_id += 1;
+        }
+
+        Ok(QuizSetPage {
+            next_cursor: items.last().map(|quiz| quiz.id),
+            items,
+            has_more,
+        })
+    }
+
+    /// 按标签筛选测验（不区分大小写），被隐藏的测验不出现在结果中
+    async fn quizzes_by_tag(&self, tag: String, limit: Option<u32>) -> Vec<QuizSetView> {
+        let tag = tag.trim().to_lowercase();
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
         let mut quiz_sets = Vec::new();
 
         let _ = self
             .state
             .quiz_sets
             .for_each_index_value(|_key, quiz| {
-                let quiz = quiz.into_owned();
-                let quiz_view = QuizSetView {
-                    id: quiz.id,
-                    title: quiz.title.clone(),
-                    description: quiz.description.clone(),
-                    creator: quiz.creator,
-                    questions: quiz
-                        .questions
-                        .iter()
-                        .map(|q| QuestionView {
-                            id: q.id,
-                            text: q.text.clone(),
-                            options: q.options.clone(),
-                            points: q.points,
-                        })
-                        .collect(),
-                    start_time: quiz.start_time.micros().to_string(),
-                    end_time: quiz.end_time.micros().to_string(),
-                    created_at: quiz.created_at.micros().to_string(),
-                };
-                quiz_sets.push(quiz_view);
+                if quiz_sets.len() < limit
+                    && !quiz.hidden
+                    && quiz.tags.iter().any(|t| t.to_lowercase() == tag)
+                {
+                    quiz_sets.push(QuizSetView::new(&quiz, self.runtime.system_time()));
+                }
                 Ok(())
             })
             .await;
@@ -83,33 +187,117 @@ impl QueryRoot {
         quiz_sets
     }
 
+    /// 获取用户的答题记录，按完成时间先后排序
     async fn user_attempts(&self, user: String) -> Vec<QuizAttempt> {
+        let Ok(user) = quiz::normalize_nick_name(&user) else {
+            return Vec::new();
+        };
         let mut attempts = Vec::new();
 
         let _ = self
             .state
             .user_attempts
-            .for_each_index_value(|(quiz_id, u), attempt| {
+            .for_each_index_value(|(_quiz_id, u), attempt| {
                 if u == user {
-                    let attempt = attempt.into_owned();
-                    let attempt_view = UserAttemptView {
-                        quiz_id: attempt.quiz_id,
-                        user: attempt.user,
-                        answers: attempt.answers,
-                        score: attempt.score,
-                        time_taken: attempt.time_taken,
-                        completed_at: attempt.completed_at.micros().to_string(),
-                    };
-                    attempts.push(QuizAttempt {
-                        quiz_id,
-                        attempt: attempt_view,
-                    });
+                    attempts.push(attempt.into_owned());
                 }
                 Ok(())
             })
             .await;
 
+        // 在转换为字符串之前按数值排序，避免不同位数的时间戳按字典序比较
+        attempts.sort_by_key(|attempt| attempt.completed_at.micros());
         attempts
+            .iter()
+            .map(|attempt| QuizAttempt {
+                quiz_id: attempt.quiz_id,
+                attempt: UserAttemptView::from(attempt),
+            })
+            .collect()
+    }
+
+    /// 同时获取测验和用户在该测验中的答题记录，用于结果页
+    async fn quiz_with_my_attempt(
+        &self,
+        quiz_id: u64,
+        user: String,
+    ) -> async_graphql::Result<QuizWithAttempt> {
+        let user = quiz::normalize_nick_name(&user)?;
+        let quiz = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await?
+            .filter(|quiz| !quiz.hidden)
+            .map(|quiz| QuizSetView::new(&quiz, self.runtime.system_time()));
+        let attempt = self
+            .state
+            .user_attempts
+            .get(&(quiz_id, user))
+            .await?
+            .map(|attempt| UserAttemptView::from(&attempt));
+        Ok(QuizWithAttempt { quiz, attempt })
+    }
+
+    /// 用户答题回顾，没有答题记录时返回空列表。测验停止接受提交前只返回用户的选择
+    async fn attempt_review(
+        &self,
+        quiz_id: u64,
+        user: String,
+    ) -> async_graphql::Result<Vec<QuestionResult>> {
+        let user = quiz::normalize_nick_name(&user)?;
+        let Some(quiz) = self.state.quiz_sets.get(&quiz_id).await? else {
+            return Ok(Vec::new());
+        };
+        let Some(attempt) = self.state.user_attempts.get(&(quiz_id, user)).await? else {
+            return Ok(Vec::new());
+        };
+        let closed = quiz.is_closed(self.runtime.system_time());
+        let question_points = quiz::score_answers(&quiz.questions, quiz.penalty, &attempt.answers)
+            .map(|scored| scored.question_points)
+            .unwrap_or_default();
+
+        Ok(quiz
+            .questions
+            .iter()
+            .zip(attempt.answers)
+            .enumerate()
+            .map(|(i, (question, selected))| QuestionResult {
+                question_id: question.id,
+                correct_options: closed.then(|| question.correct_options.clone()),
+                is_correct: closed.then(|| question.is_correct(&selected)),
+                points_awarded: if closed {
+                    question_points.get(i).copied()
+                } else {
+                    None
+                },
+                selected,
+            })
+            .collect())
+    }
+
+    /// 用户每道题是否答对，按题目顺序排列。测验停止接受提交前或没有答题记录时返回空列表
+    async fn attempt_correctness(
+        &self,
+        quiz_id: u64,
+        user: String,
+    ) -> async_graphql::Result<Vec<bool>> {
+        let user = quiz::normalize_nick_name(&user)?;
+        let Some(quiz) = self.state.quiz_sets.get(&quiz_id).await? else {
+            return Ok(Vec::new());
+        };
+        if !quiz.is_closed(self.runtime.system_time()) {
+            return Ok(Vec::new());
+        }
+        let Some(attempt) = self.state.user_attempts.get(&(quiz_id, user)).await? else {
+            return Ok(Vec::new());
+        };
+        Ok(quiz
+            .questions
+            .iter()
+            .zip(&attempt.answers)
+            .map(|(question, selected)| question.is_correct(selected))
+            .collect())
     }
 
     async fn leaderboard(&self) -> Vec<UserAttemptView> {
@@ -142,94 +330,778 @@ impl QueryRoot {
                 score,
                 time_taken,
                 completed_at: self.runtime.system_time().micros().to_string(),
+                per_question_times: Vec::new(),
+                total_points: 0, // 跨测验累计，不计算百分比
+                score_percent: 0.0,
             })
             .collect();
         leaderboard.sort_by(|a, b| b.score.cmp(&a.score).then(a.time_taken.cmp(&b.time_taken)));
         leaderboard
     }
 
-    async fn quiz_leaderboard(&self, quiz_id: u64) -> Vec<UserAttemptView> {
-        let mut entries = std::collections::HashMap::new();
+    /// 跨测验的全局排行榜：按总分降序，总分相同时参与测验数少者靠前
+    async fn global_leaderboard(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Vec<GlobalLeaderboardEntry> {
+        let mut entries = Vec::new();
+
+        let _ = self
+            .state
+            .global_scores
+            .for_each_index_value(|user, global_score| {
+                let global_score = global_score.into_owned();
+                entries.push(GlobalLeaderboardEntry {
+                    user,
+                    total_score: global_score.total_score,
+                    quizzes_played: global_score.quizzes_played,
+                    average_time: global_score
+                        .total_time
+                        .checked_div(u64::from(global_score.quizzes_played))
+                        .unwrap_or(0),
+                });
+                Ok(())
+            })
+            .await;
+
+        entries.sort_by(|a, b| {
+            b.total_score
+                .cmp(&a.total_score)
+                .then(a.quizzes_played.cmp(&b.quizzes_played))
+        });
+        entries
+            .into_iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    /// 获取某个账户题库中的问题，按ID排序
+    async fn my_bank_questions(
+        &self,
+        wallet: AccountOwner,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Vec<BankQuestionView> {
+        let mut questions = Vec::new();
+
+        let _ = self
+            .state
+            .question_bank
+            .for_each_index_value(|(owner, id), question| {
+                if owner == wallet {
+                    questions.push(BankQuestionView {
+                        id,
+                        question: question.into_owned(),
+                    });
+                }
+                Ok(())
+            })
+            .await;
+
+        questions.sort_by(|a, b| a.id.cmp(&b.id));
+        questions
+            .into_iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    /// 获取已归档测验的摘要，按测验ID排序。归档的测验不会出现在其他测验列表中
+    async fn archived_quizzes(
+        &self,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Vec<ArchivedQuizSummary> {
+        let mut summaries = Vec::new();
+
+        let _ = self
+            .state
+            .archived_quizzes
+            .for_each_index_value(|_, summary| {
+                summaries.push(summary.into_owned());
+                Ok(())
+            })
+            .await;
+
+        summaries.sort_by_key(|summary| summary.quiz_id);
+        summaries
+            .into_iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    /// 测验的评价和评论，按评价时间倒序排列
+    async fn quiz_ratings(
+        &self,
+        quiz_id: u64,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Vec<RatingView> {
+        let mut ratings = Vec::new();
+
+        let _ = self
+            .state
+            .quiz_ratings
+            .for_each_index_value(|(q_id, user), rating| {
+                if q_id == quiz_id {
+                    let rating = rating.into_owned();
+                    ratings.push((
+                        rating.rated_at,
+                        RatingView {
+                            user,
+                            stars: rating.stars,
+                            comment: rating.comment,
+                            rated_at: rating.rated_at.micros().to_string(),
+                        },
+                    ));
+                }
+                Ok(())
+            })
+            .await;
+
+        ratings.sort_by(|a, b| b.0.cmp(&a.0));
+        ratings
+            .into_iter()
+            .map(|(_, rating)| rating)
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    /// 测验参与者的昵称，默认按参与顺序排列，sort_by_name 为 true 时按昵称排序
+    async fn quiz_participants(
+        &self,
+        quiz_id: u64,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        sort_by_name: Option<bool>,
+    ) -> async_graphql::Result<Vec<String>> {
+        let participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await?
+            .unwrap_or_default();
+        Ok(quiz::page_participants(
+            participants,
+            limit,
+            offset,
+            sort_by_name.unwrap_or(false),
+        ))
+    }
+
+    /// 未处理的测验举报，按举报时间排列。服务无法验证调用者身份，
+    /// 举报内容对所有人公开，处理举报的操作由合约限制为管理员
+    async fn open_reports(&self, limit: Option<u32>) -> async_graphql::Result<Vec<ReportView>> {
+        let mut reports = Vec::new();
+
+        self.state
+            .quiz_reports
+            .for_each_index_value(|(quiz_id, owner), report| {
+                let report = report.into_owned();
+                reports.push((
+                    report.reported_at,
+                    ReportView {
+                        quiz_id,
+                        owner,
+                        user: report.user,
+                        reason: report.reason,
+                        reported_at: report.reported_at.micros().to_string(),
+                    },
+                ));
+                Ok(())
+            })
+            .await?;
+
+        reports.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(reports
+            .into_iter()
+            .map(|(_, report)| report)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect())
+    }
+
+    async fn duel(&self, duel_id: u64) -> Option<DuelView> {
+        match self.state.duels.get(&duel_id).await {
+            Ok(option) => option.map(|duel| DuelView::new(&duel, self.runtime.system_time())),
+            Err(_) => None,
+        }
+    }
+
+    /// 获取用户发起或收到的对战，可按状态筛选
+    async fn my_duels(
+        &self,
+        nickname: String,
+        status: Option<DuelStatus>,
+    ) -> async_graphql::Result<Vec<DuelView>> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
+        let now = self.runtime.system_time();
+        let duel_ids = self
+            .state
+            .user_duels
+            .get(&nickname)
+            .await?
+            .unwrap_or_default();
+        let mut duels = Vec::new();
+        for duel_id in duel_ids {
+            if let Some(duel) = self.state.duels.get(&duel_id).await? {
+                let view = DuelView::new(&duel, now);
+                if status.is_none_or(|status| view.status == status) {
+                    duels.push(view);
+                }
+            }
+        }
+        Ok(duels)
+    }
+
+    /// 团队排行榜，按测验设置的 team_ranking 排序
+    async fn team_leaderboard(
+        &self,
+        quiz_id: u64,
+    ) -> async_graphql::Result<Vec<TeamLeaderboardEntry>> {
+        let quiz = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("QuizSet not found"))?;
+        let team_count = self.state.team_counts.get(&quiz_id).await?.unwrap_or(0);
+
+        let mut entries = Vec::new();
+        for team_id in 0..team_count {
+            if let Some(team) = self.state.quiz_teams.get(&(quiz_id, team_id)).await? {
+                let member_count = team.members.len() as u32;
+                entries.push(TeamLeaderboardEntry {
+                    team_id,
+                    name: team.name,
+                    member_count,
+                    total_score: team.total_score,
+                    average_score: if member_count == 0 {
+                        0.0
+                    } else {
+                        team.total_score as f64 / f64::from(member_count)
+                    },
+                });
+            }
+        }
+
+        match quiz.team_ranking {
+            TeamRanking::TotalScore => entries.sort_by(|a, b| b.total_score.cmp(&a.total_score)),
+            TeamRanking::AverageScore => entries.sort_by(|a, b| {
+                b.average_score
+                    .partial_cmp(&a.average_score)
+                    .unwrap_or(Ordering::Equal)
+            }),
+        }
+        Ok(entries)
+    }
+
+    /// 最近的应用动态，最新的在前，默认返回20条，最多100条
+    async fn recent_activity(
+        &self,
+        limit: Option<u32>,
+    ) -> async_graphql::Result<Vec<ActivityView>> {
+        let limit = limit.unwrap_or(20).min(MAX_ACTIVITY_LIMIT) as usize;
+        let mut activity = Vec::with_capacity(limit);
+        for sequence in self.state.app_event_range.get().latest(limit).rev() {
+            if let Some(event) = self.state.app_events.get(&sequence).await? {
+                activity.push(ActivityView::from(&event));
+            }
+        }
+        Ok(activity)
+    }
+
+    /// 所有现存测验的ID，按升序排列。测验ID在归档后可能不连续
+    async fn all_quiz_ids(&self) -> async_graphql::Result<Vec<u64>> {
+        let mut quiz_ids = self.state.quiz_sets.indices().await?;
+        quiz_ids.sort_unstable();
+        Ok(quiz_ids)
+    }
+
+    /// 预览一组答案的得分，不写入任何状态。为避免泄露答案，仅在测验停止接受提交后可用
+    async fn preview_score(
+        &self,
+        quiz_id: u64,
+        answers: Vec<Vec<u32>>,
+    ) -> async_graphql::Result<ScorePreview> {
+        let quiz = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("QuizSet not found"))?;
+        if !quiz.is_closed(self.runtime.system_time()) {
+            return Err(async_graphql::Error::new(
+                "Score preview is only available after the quiz has ended",
+            ));
+        }
+        let scored = quiz::score_answers(&quiz.questions, quiz.penalty, &answers)?;
+        Ok(ScorePreview {
+            score: scored.score,
+            question_points: scored.question_points,
+        })
+    }
+
+    /// 用户获得的徽章，按获得时间排列
+    async fn user_badges(&self, nickname: String) -> async_graphql::Result<Vec<BadgeView>> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
+        let badges = self
+            .state
+            .user_badges
+            .get(&nickname)
+            .await?
+            .unwrap_or_default();
+        Ok(badges.iter().map(BadgeView::from).collect())
+    }
+
+    /// 每类徽章的获得人数
+    async fn badge_counts(&self) -> Vec<BadgeCount> {
+        let mut counts: HashMap<BadgeKind, u32> = HashMap::new();
+
+        let _ = self
+            .state
+            .user_badges
+            .for_each_index_value(|_user, badges| {
+                let kinds: HashSet<BadgeKind> = badges.iter().map(|badge| badge.kind).collect();
+                for kind in kinds {
+                    *counts.entry(kind).or_default() += 1;
+                }
+                Ok(())
+            })
+            .await;
+
+        [
+            BadgeKind::FirstQuiz,
+            BadgeKind::PerfectScore,
+            BadgeKind::SpeedDemon,
+            BadgeKind::Creator,
+        ]
+        .into_iter()
+        .map(|kind| BadgeCount {
+            kind,
+            count: counts.get(&kind).copied().unwrap_or(0),
+        })
+        .collect()
+    }
+
+    /// 获取应用配置中的各项上限
+    async fn app_config(&self) -> AppConfig {
+        self.state.config.get().clone()
+    }
+
+    /// 昵称绑定的账户，昵称尚未被使用时返回空
+    async fn nickname_owner(
+        &self,
+        nickname: String,
+    ) -> async_graphql::Result<Option<AccountOwner>> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
+        Ok(self.state.nickname_owners.get(&nickname).await?)
+    }
+
+    /// 测验排行榜，默认返回合约缓存的前N名；缓存不足 limit 条（limit 超过缓存大小，
+    /// 或缓存写入时的排行榜大小更小）且参与者更多时扫描全部答题记录
+    async fn quiz_leaderboard(&self, quiz_id: u64, limit: Option<u32>) -> Vec<UserAttemptView> {
+        let limit = limit.unwrap_or(self.state.config.get().leaderboard_size) as usize;
+        let entries = self.stored_leaderboard(quiz_id).await;
+        let participant_count = match self.state.quiz_participants.get(&quiz_id).await {
+            Ok(Some(participants)) => participants.len(),
+            _ => 0,
+        };
+        if !quiz::cached_leaderboard_covers(entries.len(), participant_count, limit) {
+            let mut leaderboard = self.scanned_quiz_leaderboard(quiz_id).await;
+            leaderboard.truncate(limit);
+            return leaderboard;
+        }
+
+        let mut leaderboard = Vec::new();
+        for entry in entries.into_iter().take(limit) {
+            let (completed_at, total_points) = match self
+                .state
+                .user_attempts
+                .get(&(quiz_id, entry.user.clone()))
+                .await
+            {
+                Ok(Some(attempt)) => (
+                    attempt.completed_at.micros().to_string(),
+                    attempt.total_points,
+                ),
+                _ => (String::new(), 0),
+            };
+            leaderboard.push(UserAttemptView {
+                quiz_id,
+                user: entry.user,
+                answers: Vec::new(),
+                score: entry.score,
+                time_taken: entry.time_taken,
+                completed_at,
+                per_question_times: Vec::new(),
+                total_points,
+                score_percent: entry.score_percent,
+            });
+        }
+        leaderboard
+    }
+
+    /// 合约中保存的测验前N名排行榜，按分数降序、用时升序排列，
+    /// 按当前配置的排行榜大小截断
+    async fn stored_leaderboard(&self, quiz_id: u64) -> Vec<LeaderboardEntry> {
+        let mut entries = match self.state.leaderboard.get(&quiz_id).await {
+            Ok(Some(entries)) => entries,
+            _ => return Vec::new(),
+        };
+        entries.truncate(self.state.config.get().leaderboard_size as usize);
+        entries
+    }
+
+    /// 单个问题的排行榜：答对该题的用户按该题用时升序排列，
+    /// 没有提交每题用时的答题记录使用整个测验的用时。
+    /// 排行榜会暴露谁答对了该题，测验停止接受提交前返回错误
+    async fn question_leaderboard(
+        &self,
+        quiz_id: u64,
+        question_id: u32,
+        limit: Option<u32>,
+    ) -> async_graphql::Result<Vec<QuestionLeaderboardEntry>> {
+        let quiz = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("QuizSet not found"))?;
+        if !quiz.is_closed(self.runtime.system_time()) {
+            return Err(async_graphql::Error::new(
+                "Question leaderboard is only available after the quiz has ended",
+            ));
+        }
+        let question = quiz
+            .questions
+            .get(question_id as usize)
+            .ok_or_else(|| async_graphql::Error::new("Question not found"))?;
+
+        let participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await?
+            .unwrap_or_default();
+        let mut entries = Vec::new();
+        for user in participants {
+            let Some(attempt) = self.state.user_attempts.get(&(quiz_id, user)).await? else {
+                continue;
+            };
+            let correct = attempt
+                .answers
+                .get(question_id as usize)
+                .is_some_and(|selected| question.is_correct(selected));
+            if correct {
+                entries.push(QuestionLeaderboardEntry {
+                    time_taken: attempt
+                        .per_question_times
+                        .get(question_id as usize)
+                        .copied()
+                        .unwrap_or(attempt.time_taken),
+                    user: attempt.user,
+                });
+            }
+        }
+
+        entries.sort_by_key(|entry| entry.time_taken);
+        Ok(entries
+            .into_iter()
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect())
+    }
+
+    /// 用户在测验中的名次，排序规则与 quiz_leaderboard 一致（分数降序、用时升序）
+    async fn quiz_rank(&self, quiz_id: u64, user: String) -> Option<QuizRankView> {
+        self.user_rank(quiz_id, &user).await
+    }
+
+    /// 用户在测验中的成绩、名次和百分位
+    async fn user_quiz_result(&self, quiz_id: u64, user: String) -> Option<UserQuizResult> {
+        let rank = self.user_rank(quiz_id, &user).await?;
+        Some(UserQuizResult {
+            score: rank.score,
+            time_taken: rank.time_taken,
+            rank: rank.rank,
+            // 名次不低于该用户的参与者所占百分比，第一名为100
+            percentile: f64::from(rank.total_participants - rank.rank + 1) * 100.0
+                / f64::from(rank.total_participants),
+            total_participants: rank.total_participants,
+        })
+    }
+
+    /// 测验中每个问题的答题统计，没有答题记录时返回全零统计。
+    /// 测验停止接受提交前只返回作答人数，避免泄露答案
+    async fn quiz_question_stats(&self, quiz_id: u64) -> Vec<QuestionStatsView> {
+        let Ok(Some(quiz)) = self.state.quiz_sets.get(&quiz_id).await else {
+            return Vec::new();
+        };
+        let closed = quiz.is_closed(self.runtime.system_time());
+        let mut attempts = vec![0u32; quiz.questions.len()];
+        let mut correct_counts = vec![0u32; quiz.questions.len()];
+        let mut option_counts: Vec<Vec<u32>> = quiz
+            .questions
+            .iter()
+            .map(|q| vec![0; q.options.len()])
+            .collect();
+        let mut total_points = vec![0u64; quiz.questions.len()];
 
         let _ = self
             .state
             .user_attempts
-            .for_each_index_value(|(q_id, user), attempt| {
+            .for_each_index_value(|(q_id, _user), attempt| {
                 if q_id == quiz_id {
-                    let attempt = attempt.into_owned();
-                    let entry = entries.entry(user).or_insert((0, u64::MAX, String::new()));
-                    if attempt.score > entry.0
-                        || (attempt.score == entry.0 && attempt.time_taken < entry.1)
+                    // 忽略与当前问题列表不对应的答案
+                    for (i, (question, selected)) in quiz
+                        .questions
+                        .iter()
+                        .zip(attempt.answers.iter())
+                        .enumerate()
                     {
-                        entry.0 = attempt.score;
-                        entry.1 = attempt.time_taken;
-                        entry.2 = attempt.completed_at.micros().to_string();
+                        attempts[i] += 1;
+                        for &option in selected {
+                            if let Some(count) = option_counts[i].get_mut(option as usize) {
+                                *count += 1;
+                            }
+                        }
+                        if question.is_correct(selected) {
+                            correct_counts[i] += 1;
+                            total_points[i] += u64::from(question.points);
+                        }
                     }
                 }
                 Ok(())
             })
             .await;
 
-        let mut leaderboard: Vec<_> = entries
-            .into_iter()
+        quiz.questions
+            .iter()
+            .zip(attempts)
+            .zip(correct_counts)
+            .zip(option_counts)
+            .zip(total_points)
             .map(
-                |(user, (score, time_taken, completed_at))| UserAttemptView {
-                    quiz_id,
-                    user,
-                    answers: Vec::new(),
-                    score,
-                    time_taken,
-                    completed_at: completed_at,
+                |((((question, attempts), correct_count), option_counts), points)| {
+                    let average_points = if attempts > 0 {
+                        points as f64 / f64::from(attempts)
+                    } else {
+                        0.0
+                    };
+                    QuestionStatsView {
+                        question_id: question.id,
+                        attempts,
+                        correct_count: closed.then_some(correct_count),
+                        option_counts: closed.then_some(option_counts),
+                        average_points: closed.then_some(average_points),
+                    }
                 },
             )
-            .collect();
-        leaderboard.sort_by(|a, b| b.score.cmp(&a.score).then(a.time_taken.cmp(&b.time_taken)));
-        leaderboard
+            .collect()
+    }
+
+    /// 测验汇总统计：参与人数、平均分、最高分以及用时的中位数和平均数
+    async fn quiz_summary(&self, quiz_id: u64) -> async_graphql::Result<QuizSummaryView> {
+        let mut total_score = 0u64;
+        let mut highest_score = 0;
+        let mut times = Vec::new();
+
+        for attempt in self.participant_attempts(quiz_id).await? {
+            total_score += u64::from(attempt.score);
+            highest_score = highest_score.max(attempt.score);
+            times.push(attempt.time_taken);
+        }
+
+        let count = times.len() as u64;
+        times.sort_unstable();
+        Ok(QuizSummaryView {
+            quiz_id,
+            participant_count: times.len() as u32,
+            average_score: if count == 0 {
+                0.0
+            } else {
+                total_score as f64 / count as f64
+            },
+            highest_score,
+            median_time_taken: times
+                .get(times.len().saturating_sub(1) / 2)
+                .copied()
+                .unwrap_or(0),
+            mean_time_taken: times.iter().sum::<u64>().checked_div(count).unwrap_or(0),
+        })
+    }
+
+    /// 将测验定义导出为带版本号的JSON。导出内容包含正确答案，
+    /// 且服务无法验证调用者身份，因此只在测验停止接受提交后可用，被隐藏的测验不能导出
+    async fn export_quiz(&self, quiz_id: u64) -> async_graphql::Result<String> {
+        let quiz = self
+            .state
+            .quiz_sets
+            .get(&quiz_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("QuizSet not found"))?;
+        if quiz.hidden {
+            return Err(async_graphql::Error::new("Quiz is hidden"));
+        }
+        if !quiz.is_closed(self.runtime.system_time()) {
+            return Err(async_graphql::Error::new(
+                "Quiz can only be exported after it has ended",
+            ));
+        }
+        Ok(serde_json::to_string(&QuizExport::from(&quiz))?)
+    }
+
+    /// 导出测验成绩为CSV文本，按提交顺序排列
+    async fn export_results_csv(&self, quiz_id: u64) -> async_graphql::Result<String> {
+        let mut csv = String::from("nickname,score,time_taken,completed_at\n");
+
+        for attempt in self.participant_attempts(quiz_id).await? {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&attempt.user),
+                attempt.score,
+                attempt.time_taken,
+                attempt.completed_at.micros()
+            ));
+        }
+
+        Ok(csv)
     }
 
     async fn user_participations(&self, user: String) -> Vec<u64> {
+        let Ok(user) = quiz::normalize_nick_name(&user) else {
+            return Vec::new();
+        };
         match self.state.user_participations.get(&user).await {
             Ok(Some(v)) => v,
             Ok(None) => Vec::default(),
             Err(_) => Vec::default(),
         }
     }
-    async fn get_user_created_quizzes(&self, nickname: String) -> Vec<QuizSetView> {
+
+    /// 以带版本号的JSON导出用户的数据：参与的测验、创建的测验和答题记录（含每题答案）。
+    /// 答题记录按参与顺序每页50条，page 从0开始
+    async fn export_my_data(
+        &self,
+        nickname: String,
+        page: Option<u32>,
+    ) -> async_graphql::Result<String> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
+        let page = page.unwrap_or(0);
+        let participations = self
+            .state
+            .user_participations
+            .get(&nickname)
+            .await?
+            .unwrap_or_default();
+        let created_quiz_ids = self
+            .state
+            .user_created_quizzes
+            .get(&nickname)
+            .await?
+            .unwrap_or_default();
+
+        let start = (page as usize).saturating_mul(USER_DATA_EXPORT_PAGE_SIZE);
+        let mut attempts = Vec::new();
+        for &quiz_id in participations
+            .iter()
+            .skip(start)
+            .take(USER_DATA_EXPORT_PAGE_SIZE)
+        {
+            if let Some(attempt) = self
+                .state
+                .user_attempts
+                .get(&(quiz_id, nickname.clone()))
+                .await?
+            {
+                attempts.push(UserAttemptView::from(&attempt));
+            }
+        }
+
+        let export = UserDataExport {
+            version: USER_DATA_EXPORT_VERSION,
+            has_more: participations.len() > start.saturating_add(USER_DATA_EXPORT_PAGE_SIZE),
+            user: nickname,
+            page,
+            participations,
+            created_quiz_ids,
+            attempts,
+        };
+        Ok(serde_json::to_string(&export)?)
+    }
+
+    async fn get_user_created_quizzes(
+        &self,
+        nickname: String,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizSetView>> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
         let mut created_quizzes = Vec::new();
-        let _ = self
+        let now = self.runtime.system_time();
+        let quiz_ids = self
             .state
-            .quiz_sets
-            .for_each_index_value(|_key, quiz| {
-                let quiz = quiz.into_owned();
-                if quiz.creator == nickname {
-                    created_quizzes.push(QuizSetView {
-                        id: quiz.id,
-                        title: quiz.title.clone(),
-                        description: quiz.description.clone(),
-                        creator: quiz.creator,
-                        questions: quiz
-                            .questions
-                            .iter()
-                            .map(|q| QuestionView {
-                                id: q.id,
-                                text: q.text.clone(),
-                                options: q.options.clone(),
-                                points: q.points,
-                            })
-                            .collect(),
-                        start_time: quiz.start_time.micros().to_string(),
-                        end_time: quiz.end_time.micros().to_string(),
-                        created_at: quiz.created_at.micros().to_string(),
-                    });
+            .user_created_quizzes
+            .get(&nickname)
+            .await?
+            .unwrap_or_default();
+        for quiz_id in quiz_ids {
+            if let Some(quiz_set) = self.state.quiz_sets.get(&quiz_id).await? {
+                if !quiz_set.hidden {
+                    created_quizzes.push(quiz_set);
                 }
-                Ok(())
-            })
-            .await;
-        created_quizzes
+            }
+        }
+        self.sort_quiz_sets(&mut created_quizzes, sort_by, descending)
+            .await?;
+        Ok(created_quizzes
+            .into_iter()
+            .map(|quiz| QuizSetView::new(&quiz, now))
+            .collect())
+    }
+
+    /// 获取某个账户创建的测验，与创建时使用的昵称无关
+    async fn created_quizzes_by_wallet(
+        &self,
+        wallet: AccountOwner,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizSetView>> {
+        let mut created_quizzes = Vec::new();
+        let now = self.runtime.system_time();
+        let quiz_ids = self
+            .state
+            .owner_created_quizzes
+            .get(&wallet)
+            .await?
+            .unwrap_or_default();
+        for quiz_id in quiz_ids {
+            if let Some(quiz_set) = self.state.quiz_sets.get(&quiz_id).await? {
+                if !quiz_set.hidden {
+                    created_quizzes.push(quiz_set);
+                }
+            }
+        }
+        self.sort_quiz_sets(&mut created_quizzes, sort_by, descending)
+            .await?;
+        Ok(created_quizzes
+            .into_iter()
+            .map(|quiz| QuizSetView::new(&quiz, now))
+            .collect())
     }
 
-    async fn get_user_participated_quizzes(&self, nickname: String) -> Vec<QuizSetView> {
+    async fn get_user_participated_quizzes(
+        &self,
+        nickname: String,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizSetView>> {
+        let nickname = quiz::normalize_nick_name(&nickname)?;
         let mut participated_quizzes = Vec::new();
+        let now = self.runtime.system_time();
         let quiz_ids = self
             .state
             .user_participations
@@ -238,29 +1110,163 @@ impl QueryRoot {
             .unwrap()
             .unwrap_or_default();
         for &quiz_id in &quiz_ids {
-            if let Some(quiz_set) = self.state.quiz_sets.get(&quiz_id).await.unwrap() {
-                participated_quizzes.push(QuizSetView {
-                    id: quiz_set.id,
-                    title: quiz_set.title.clone(),
-                    description: quiz_set.description.clone(),
-                    creator: quiz_set.creator.clone(),
-                    questions: quiz_set
-                        .questions
-                        .iter()
-                        .map(|q| QuestionView {
-                            id: q.id,
-                            text: q.text.clone(),
-                            options: q.options.clone(),
-                            points: q.points,
-                        })
-                        .collect(),
-                    start_time: quiz_set.start_time.micros().to_string(),
-                    end_time: quiz_set.end_time.micros().to_string(),
-                    created_at: quiz_set.created_at.micros().to_string(),
-                });
+            if let Some(quiz_set) = self.state.quiz_sets.get(&quiz_id).await? {
+                if !quiz_set.hidden {
+                    participated_quizzes.push(quiz_set);
+                }
             }
         }
-        participated_quizzes
+        self.sort_quiz_sets(&mut participated_quizzes, sort_by, descending)
+            .await?;
+        Ok(participated_quizzes
+            .into_iter()
+            .map(|quiz| QuizSetView::new(&quiz, now))
+            .collect())
+    }
+}
+
+impl QueryRoot {
+    /// 按参与顺序读取测验的全部答题记录，只按键查询，不扫描其他测验的记录
+    async fn participant_attempts(&self, quiz_id: u64) -> async_graphql::Result<Vec<UserAttempt>> {
+        let participants = self
+            .state
+            .quiz_participants
+            .get(&quiz_id)
+            .await?
+            .unwrap_or_default();
+        let mut attempts = Vec::with_capacity(participants.len());
+        for user in participants {
+            if let Some(attempt) = self.state.user_attempts.get(&(quiz_id, user)).await? {
+                attempts.push(attempt);
+            }
+        }
+        Ok(attempts)
+    }
+
+    /// 扫描全部答题记录计算测验排行榜，顺序与缓存的排行榜一致
+    async fn scanned_quiz_leaderboard(&self, quiz_id: u64) -> Vec<UserAttemptView> {
+        let mut attempts = Vec::new();
+
+        let _ = self
+            .state
+            .user_attempts
+            .for_each_index_value(|(q_id, _user), attempt| {
+                if q_id == quiz_id {
+                    attempts.push(attempt.into_owned());
+                }
+                Ok(())
+            })
+            .await;
+
+        quiz::rank_attempts(attempts)
+            .into_iter()
+            .map(|attempt| {
+                let entry = LeaderboardEntry::from(&attempt);
+                UserAttemptView {
+                    quiz_id,
+                    user: entry.user,
+                    answers: Vec::new(),
+                    score: entry.score,
+                    time_taken: entry.time_taken,
+                    completed_at: attempt.completed_at.micros().to_string(),
+                    per_question_times: Vec::new(),
+                    total_points: attempt.total_points,
+                    score_percent: entry.score_percent,
+                }
+            })
+            .collect()
+    }
+
+    /// 按状态和创建者筛选测验并排序
+    async fn filtered_quiz_sets(
+        &self,
+        status: Option<QuizStatus>,
+        creator: Option<String>,
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<Vec<QuizSet>> {
+        let creator = creator
+            .map(|creator| quiz::normalize_nick_name(&creator))
+            .transpose()?;
+        let mut quiz_sets = Vec::new();
+        let now = self.runtime.system_time();
+
+        self.state
+            .quiz_sets
+            .for_each_index_value(|_key, quiz| {
+                if quiz.hidden
+                    || status.is_some_and(|status| quiz.status(now) != status)
+                    || creator
+                        .as_ref()
+                        .is_some_and(|creator| quiz.creator != *creator)
+                {
+                    return Ok(());
+                }
+                quiz_sets.push(quiz.into_owned());
+                Ok(())
+            })
+            .await?;
+
+        self.sort_quiz_sets(&mut quiz_sets, sort_by, descending)
+            .await?;
+        Ok(quiz_sets)
+    }
+
+    /// 按 sort_by 指定的字段排序测验列表，按参与人数排序时才读取参与者列表
+    async fn sort_quiz_sets(
+        &self,
+        quiz_sets: &mut [QuizSet],
+        sort_by: Option<QuizSortField>,
+        descending: Option<bool>,
+    ) -> async_graphql::Result<()> {
+        let mut counts = HashMap::new();
+        if sort_by == Some(QuizSortField::ParticipantCount) {
+            for quiz in quiz_sets.iter() {
+                let count = self
+                    .state
+                    .quiz_participants
+                    .get(&quiz.id)
+                    .await?
+                    .map_or(0, |participants| participants.len());
+                counts.insert(quiz.id, count);
+            }
+        }
+        quiz::sort_quiz_sets(quiz_sets, sort_by, descending.unwrap_or(false), &counts);
+        Ok(())
+    }
+
+    /// 按分数降序、用时升序计算用户名次，成绩相同的用户名次相同
+    async fn user_rank(&self, quiz_id: u64, user: &str) -> Option<QuizRankView> {
+        let user = quiz::normalize_nick_name(user).ok()?;
+        let attempt = self
+            .state
+            .user_attempts
+            .get(&(quiz_id, user))
+            .await
+            .ok()??;
+        let results = self.participant_attempts(quiz_id).await.ok()?;
+        let ahead = results
+            .iter()
+            .filter(|other| {
+                other.score > attempt.score
+                    || (other.score == attempt.score && other.time_taken < attempt.time_taken)
+            })
+            .count();
+        Some(QuizRankView {
+            rank: ahead as u32 + 1,
+            total_participants: results.len() as u32,
+            score: attempt.score,
+            time_taken: attempt.time_taken,
+        })
+    }
+}
+
+/// 按CSV规则转义字段：包含逗号、引号或换行时用双引号包裹
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
     }
 }
 
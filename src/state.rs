@@ -1,10 +1,8 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_sdk::linera_base_types::Timestamp;
-use linera_sdk::views::{
-    linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext,
-};
+use linera_sdk::linera_base_types::{AccountOwner, Amount, TimeDelta, Timestamp};
+use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
 use serde::{Deserialize, Serialize};
 
 /// 问题结构
@@ -15,6 +13,21 @@ pub struct Question {
     pub options: Vec<String>,
     pub correct_options: Vec<u32>,
     pub points: u32,
+    pub image_url: Option<String>,
+    pub option_images: Option<Vec<Option<String>>>, // 与 options 一一对应
+    pub media_url: Option<String>,                  // 图片或音频
+    pub single_choice: bool,                        // 单选题只能选择一个选项
+}
+
+impl Question {
+    /// 检查用户选择的答案是否与所有正确选项完全匹配（顺序无关）
+    pub fn is_correct(&self, selected: &[u32]) -> bool {
+        let mut selected_sorted = selected.to_vec();
+        selected_sorted.sort();
+        let mut correct_options_sorted = self.correct_options.clone();
+        correct_options_sorted.sort();
+        selected_sorted == correct_options_sorted
+    }
 }
 
 /// Quiz集合结构
@@ -24,11 +37,122 @@ pub struct QuizSet {
     pub title: String,
     pub description: String,
     pub creator: String,
+    pub creator_owner: AccountOwner, // 创建测验的签名账户
     pub questions: Vec<Question>,
     pub time_limit: u64, // 秒
     pub start_time: Timestamp,
     pub end_time: Timestamp,
     pub created_at: Timestamp,
+    pub tags: Vec<String>,
+    pub max_participants: Option<u32>,
+    pub allow_cloning: bool,       // 是否允许其他用户以此测验为模板创建新测验
+    pub image_url: Option<String>, // 封面图片
+    pub penalty: Option<u32>,      // 每道错题扣除的分数
+    pub team_ranking: super::TeamRanking,
+    pub delivery_mode: super::DeliveryMode,
+    pub revealed_questions: u32,           // 实时模式下已发放的题目数量
+    pub question_open: bool,               // 实时模式下最后发放的题目是否仍在作答中
+    pub question_revealed_at: Timestamp,   // 实时模式下最后一题的发放时间
+    pub grace_period_secs: u64,            // 结束后仍接受提交的宽限时间
+    pub allow_creator_participation: bool, // 是否允许创建者参与自己的测验
+    pub total_points: u32,                 // 所有题目分值之和
+    pub rating_count: u32,                 // 评价数量
+    pub rating_total: u64,                 // 所有评价的星级之和
+    pub hidden: bool,                      // 被管理员隐藏，不出现在列表中且不接受提交
+    pub report_count: u32,                 // 未处理的举报数量
+    pub prize: Amount,                     // 托管在应用账户中的奖金
+    pub prize_split: Vec<u8>,              // 前几名分得奖金的百分比
+    pub prize_distributed: bool,           // 奖金是否已发放或退还
+    pub entry_fee: Amount,                 // 每位参与者的报名费
+    pub pool_total: Amount,                // 托管奖金加已收取的报名费，退款后为0
+    pub cancelled: bool,                   // 已取消并退款，不再接受提交
+}
+
+impl QuizSet {
+    /// 可以展示给参与者的题目数量，实时模式下只包含已发放的题目
+    pub fn visible_question_count(&self) -> usize {
+        match self.delivery_mode {
+            super::DeliveryMode::AllAtOnce => self.questions.len(),
+            super::DeliveryMode::Live => self.revealed_questions as usize,
+        }
+    }
+
+    /// 平均星级，没有评价时为0
+    pub fn average_rating(&self) -> f64 {
+        if self.rating_count == 0 {
+            0.0
+        } else {
+            self.rating_total as f64 / f64::from(self.rating_count)
+        }
+    }
+
+    /// 是否已结束（当前时间超过结束时间）
+    pub fn is_ended(&self, now: Timestamp) -> bool {
+        now > self.end_time
+    }
+
+    /// 是否已停止接受提交（当前时间超过结束时间加宽限时间），之后才能公开答案相关的数据
+    pub fn is_closed(&self, now: Timestamp) -> bool {
+        let grace_period = TimeDelta::from_secs(self.grace_period_secs);
+        now > self.end_time.saturating_add(grace_period)
+    }
+
+    /// 参与人数是否已达上限，没有上限时永远不满
+    pub fn is_full(&self, participant_count: usize) -> bool {
+        self.max_participants
+            .is_some_and(|max_participants| participant_count >= max_participants as usize)
+    }
+
+    /// 创建者和管理员可以管理测验（归档、退款）
+    pub fn can_be_managed_by(&self, owner: &AccountOwner, config: &super::AppConfig) -> bool {
+        self.creator_owner == *owner || config.is_admin(owner)
+    }
+
+    /// 测验不允许创建者参与时，拒绝创建者签名账户；没有签名账户的提交不受限制
+    pub fn allows_participant(&self, signer: Option<&AccountOwner>) -> bool {
+        self.allow_creator_participation || signer != Some(&self.creator_owner)
+    }
+
+    /// 创建者只能在测验开始前取消并退款，管理员在奖金发放前随时可以退款
+    pub fn can_be_refunded_by(
+        &self,
+        owner: &AccountOwner,
+        config: &super::AppConfig,
+        now: Timestamp,
+    ) -> bool {
+        !self.prize_distributed
+            && (config.is_admin(owner) || (self.creator_owner == *owner && now < self.start_time))
+    }
+
+    /// 将一份报名费计入奖池
+    pub fn add_entry_fee(&mut self) {
+        assert!(!self.cancelled, "Quiz has been cancelled");
+        assert!(
+            !self.prize_distributed,
+            "Quiz prizes have already been settled"
+        );
+        self.pool_total = self.pool_total.saturating_add(self.entry_fee);
+    }
+
+    /// 奖池全部退还后取消测验，之后不再接受提交、报名费或奖金发放
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.prize_distributed = true;
+        self.pool_total = Amount::ZERO;
+    }
+
+    /// 根据当前时间计算测验状态，已取消的测验优先返回 Cancelled
+    pub fn status(&self, now: Timestamp) -> super::QuizStatus {
+        if self.cancelled {
+            super::QuizStatus::Cancelled
+        } else if now < self.start_time {
+            super::QuizStatus::Upcoming
+        } else if self.is_ended(now) {
+            super::QuizStatus::Ended
+        } else {
+            super::QuizStatus::Live
+        }
+    }
 }
 
 /// 用户答题尝试
@@ -40,9 +164,92 @@ pub struct UserAttempt {
     pub score: u32,
     pub time_taken: u64, // 毫秒
     pub completed_at: Timestamp,
+    pub per_question_times: Vec<u64>, // 每个问题的用时（毫秒），未提供时为空
+    pub total_points: u32,            // 提交时测验的总分
+}
+
+/// 应用动态，记录测验创建和答案提交
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppEvent {
+    pub kind: super::ActivityKind,
+    pub quiz_id: u64,
+    pub user: String,
+    pub timestamp: Timestamp,
+}
+
+/// 用户获得的徽章
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Badge {
+    pub kind: super::BadgeKind,
+    pub quiz_id: u64, // 获得徽章的测验
+    pub awarded_at: Timestamp,
+}
+
+/// 参与者对测验的评价
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rating {
+    pub stars: u8,
+    pub comment: Option<String>,
+    pub rated_at: Timestamp,
+}
+
+/// 用户对测验的举报
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Report {
+    pub user: String, // 举报时使用的昵称
+    pub reason: String,
+    pub reported_at: Timestamp,
+}
+
+/// 两个用户在同一测验上的对战
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Duel {
+    pub id: u64,
+    pub quiz_id: u64,
+    pub challenger: String,
+    pub opponent: String,
+    pub deadline: Timestamp, // 对手接受的截止时间
+    pub status: super::DuelStatus,
+    pub winner: Option<String>,
+}
+
+impl Duel {
+    /// 超过截止时间仍未接受的对战视为已过期
+    pub fn status(&self, now: Timestamp) -> super::DuelStatus {
+        if self.status == super::DuelStatus::Pending && now > self.deadline {
+            super::DuelStatus::Expired
+        } else {
+            self.status
+        }
+    }
+}
+
+/// 实时模式下用户对单个题目的作答
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiveAnswer {
+    pub selected: Vec<u32>,
+    pub time_taken: u64, // 毫秒，从题目发放到提交的用时
+}
+
+/// 测验中的团队
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Team {
+    pub id: u32,
+    pub name: String,
+    pub members: Vec<String>,
+    pub total_score: u64,
+}
+
+/// 用户跨测验的累计成绩
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GlobalScore {
+    pub total_score: u64,
+    pub quizzes_played: u32,
+    pub total_time: u64, // 毫秒
 }
 
-/// Quiz应用状态
+/// Quiz应用状态。视图中的值以BCS编码存储，按字段顺序解析且不支持 serde(default)，
+/// 修改任何存储结构的字段都会导致已有数据无法读取，需要部署新的应用
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct QuizState {
@@ -50,12 +257,62 @@ pub struct QuizState {
     pub quiz_sets: MapView<u64, QuizSet>,
     /// 存储用户答题尝试 ((QuizId, User) -> UserAttempt)
     pub user_attempts: MapView<(u64, String), UserAttempt>,
-    /// 记录答题事件用于排行榜计算
-    pub quiz_events: LogView<UserAttempt>,
+    /// 记录答题事件用于排行榜计算，按序号存储以便裁剪 (Sequence -> UserAttempt)
+    pub quiz_events: MapView<u64, UserAttempt>,
+    /// quiz_events 中仍保留的序号范围
+    pub quiz_event_range: RegisterView<super::EventRange>,
     /// 下一个可用的Quiz ID
     pub next_quiz_id: RegisterView<u64>,
     /// 用户参与的测验集合 (User -> Vec<QuizId>)
     pub user_participations: MapView<String, Vec<u64>>,
     /// 测验排行榜 (QuizId -> Vec<super::LeaderboardEntry>)
     pub leaderboard: MapView<u64, Vec<super::LeaderboardEntry>>,
+    /// 用户跨测验的累计成绩 (User -> GlobalScore)
+    pub global_scores: MapView<String, GlobalScore>,
+    /// 测验的参与用户 (QuizId -> Vec<User>)
+    pub quiz_participants: MapView<u64, Vec<String>>,
+    /// 用户创建的测验 (Creator -> Vec<QuizId>)
+    pub user_created_quizzes: MapView<String, Vec<u64>>,
+    /// 账户创建的测验 (Owner -> Vec<QuizId>)，不受昵称变化影响
+    pub owner_created_quizzes: MapView<AccountOwner, Vec<u64>>,
+    /// 创建者的题库 ((Owner, BankQuestionId) -> super::QuestionParams)
+    pub question_bank: MapView<(AccountOwner, String), super::QuestionParams>,
+    /// 实例化时设置的应用配置
+    pub config: RegisterView<super::AppConfig>,
+    /// 账户最近24小时内创建测验的时间 (Owner -> Vec<Timestamp>)
+    pub creation_timestamps: MapView<AccountOwner, Vec<Timestamp>>,
+    /// 已归档测验的摘要 (QuizId -> super::ArchivedQuizSummary)
+    pub archived_quizzes: MapView<u64, super::ArchivedQuizSummary>,
+    /// 所有对战 (DuelId -> Duel)
+    pub duels: MapView<u64, Duel>,
+    /// 下一个可用的对战ID
+    pub next_duel_id: RegisterView<u64>,
+    /// 用户参与的对战 (User -> Vec<DuelId>)
+    pub user_duels: MapView<String, Vec<u64>>,
+    /// 测验中的团队 ((QuizId, TeamId) -> Team)
+    pub quiz_teams: MapView<(u64, u32), Team>,
+    /// 测验中已创建的团队数量，同时作为下一个团队ID (QuizId -> u32)
+    pub team_counts: MapView<u64, u32>,
+    /// 用户在测验中所属的团队 ((QuizId, User) -> TeamId)
+    pub user_team: MapView<(u64, String), u32>,
+    /// 实时模式下用户的逐题作答 ((QuizId, User) -> 每题的作答，未作答为空)
+    pub live_answers: MapView<(u64, String), Vec<Option<LiveAnswer>>>,
+    /// 实时模式下已作答的用户 (QuizId -> Vec<User>)
+    pub live_participants: MapView<u64, Vec<String>>,
+    /// 应用动态，按时间顺序分配序号 (Sequence -> AppEvent)
+    pub app_events: MapView<u64, AppEvent>,
+    /// app_events 中仍保留的序号范围
+    pub app_event_range: RegisterView<super::EventRange>,
+    /// 用户获得的徽章 (User -> Vec<Badge>)
+    pub user_badges: MapView<String, Vec<Badge>>,
+    /// 测验中所有答题用时，升序排列 (QuizId -> Vec<TimeTaken>)
+    pub quiz_times: MapView<u64, Vec<u64>>,
+    /// 测验评价 ((QuizId, User) -> Rating)
+    pub quiz_ratings: MapView<(u64, String), Rating>,
+    /// 未处理的测验举报，按举报账户区分，更换昵称不能重复举报 ((QuizId, Owner) -> Report)
+    pub quiz_reports: MapView<(u64, AccountOwner), Report>,
+    /// 参与者提交答案时的签名账户，用于发放奖金 ((QuizId, User) -> AccountOwner)
+    pub participant_owners: MapView<(u64, String), AccountOwner>,
+    /// 昵称绑定的账户，首次使用昵称时绑定，之后只能由该账户使用 (User -> AccountOwner)
+    pub nickname_owners: MapView<String, AccountOwner>,
 }